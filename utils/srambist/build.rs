@@ -0,0 +1,105 @@
+//! Generates register maps from the `REG_WIDTH` maps of the Scala RTL, so
+//! that host code cannot drift from the hardware.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+const CTRL_REGS: &str =
+    "../../generators/stac-controller/src/main/scala/StacControllerCtrlRegs.scala";
+
+/// The Scala objects to generate maps for, with the module to generate and
+/// the base address of the peripheral.
+const PERIPHERALS: [(&str, &str, u64); 2] = [
+    ("SramBistCtrlRegs", "sram_bist", 0x1000),
+    ("StacControllerCtrlRegs", "stac_controller", 0x9000_0000),
+];
+
+fn main() {
+    println!("cargo:rerun-if-changed={CTRL_REGS}");
+    let scala = fs::read_to_string(CTRL_REGS)
+        .unwrap_or_else(|err| panic!("failed to read {CTRL_REGS}: {err}"));
+
+    let mut out = String::new();
+    for (object, module, base) in PERIPHERALS {
+        let body = object_body(&scala, object);
+        let mut regs = reg_widths(body);
+        // Registers appended to `REGMAP_OFFSET` without a width, such as
+        // `EX`, are write triggers that occupy a single word.
+        for name in trailing_regs(body) {
+            regs.push((name, 64));
+        }
+
+        writeln!(out, "/// Registers of `{object}`.").unwrap();
+        writeln!(out, "pub mod {module} {{").unwrap();
+        writeln!(out, "    use super::Reg;").unwrap();
+        writeln!(out, "    pub const BASE: u64 = {base:#x};").unwrap();
+        let mut offset = 0;
+        for (name, width) in &regs {
+            writeln!(
+                out,
+                "    pub const {name}: Reg = Reg::new(\"{name}\", BASE, {offset:#x}, {width});"
+            )
+            .unwrap();
+            offset += ((width - 1) / 64 + 1) * 8;
+        }
+        let names: Vec<&str> = regs.iter().map(|(name, _)| name.as_str()).collect();
+        writeln!(out, "    pub const ALL: &[Reg] = &[{}];", names.join(", ")).unwrap();
+        writeln!(out, "}}").unwrap();
+    }
+
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("regmap.rs");
+    fs::write(dest, out).unwrap();
+}
+
+/// The text of `object NAME extends Enumeration { ... }`.
+fn object_body<'a>(scala: &'a str, object: &str) -> &'a str {
+    let start = scala
+        .find(&format!("object {object} "))
+        .unwrap_or_else(|| panic!("{object} not found in {CTRL_REGS}"));
+    let body = &scala[start..];
+    let end = body[1..]
+        .find("\nobject ")
+        .map_or(body.len(), |end| end + 1);
+    &body[..end]
+}
+
+/// The `NAME -> width` entries of `REG_WIDTH`, in order.
+fn reg_widths(body: &str) -> Vec<(String, u64)> {
+    let start = body
+        .find("REG_WIDTH = LinkedHashMap(")
+        .expect("REG_WIDTH not found");
+    let map = &body[start..];
+    let map = &map[map.find('(').unwrap() + 1..map.find(')').unwrap()];
+    map.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (name, width) = entry
+                .split_once("->")
+                .unwrap_or_else(|| panic!("malformed REG_WIDTH entry {entry:?}"));
+            let width = width
+                .trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("malformed REG_WIDTH entry {entry:?}"));
+            (name.trim().to_string(), width)
+        })
+        .collect()
+}
+
+/// The registers appended to the keys of `REGMAP_OFFSET` with `Iterator(...)`.
+fn trailing_regs(body: &str) -> Vec<String> {
+    let Some(start) = body.find("REGMAP_OFFSET") else {
+        return Vec::new();
+    };
+    let map = &body[start..];
+    let Some(start) = map.find("Iterator(") else {
+        return Vec::new();
+    };
+    let args = &map[start + "Iterator(".len()..];
+    args[..args.find(')').unwrap()]
+        .split(',')
+        .map(|name| name.trim().to_string())
+        .collect()
+}
@@ -1,8 +1,15 @@
 use crate::executor::Executor;
+#[cfg(feature = "unverified-bist")]
+use crate::mmio::BistExecutor;
+use crate::mmio::{MmioExecutor, SramBist, Transport};
 use crate::pattern::{SramAddr, SramWord};
+use std::io::{self, Read, Write};
 use std::process::Command;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
 
-pub fn bebe_write(addr: u64, data: u64, len: u64) {
+pub fn bebe_write(addr: u64, data: u64, len: u64) -> io::Result<()> {
     let addr = format!("{addr:X}");
     let data = format!("{data:X}");
     let len = format!("{len}");
@@ -19,14 +26,14 @@ pub fn bebe_write(addr: u64, data: u64, len: u64) {
             "--wlen",
             &len,
         ])
-        .status()
-        .expect("failed to run bebe");
+        .status()?;
     if !status.success() {
-        panic!("bebe exited with non-zero exit code")
+        return Err(io::Error::other(format!("bebe exited with {status}")));
     }
+    Ok(())
 }
 
-pub fn bebe_read(addr: u64, len: u64) -> u64 {
+pub fn bebe_read(addr: u64, len: u64) -> io::Result<u64> {
     let addr = format!("{addr:X}");
     let len = format!("{len}");
     let output = Command::new("sudo")
@@ -40,66 +47,221 @@ pub fn bebe_read(addr: u64, len: u64) -> u64 {
             "--rlen",
             &len,
         ])
-        .output()
-        .expect("failed to run bebe");
-    let output = String::from_utf8(output.stdout).expect("failed to parse bebe output");
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "bebe exited with {}",
+            output.status
+        )));
+    }
+    let output = String::from_utf8(output.stdout)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
     output
         .trim()
         .parse()
-        .expect("failed to convert bebe output to u64")
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
 }
 
-pub struct BebeExecutor {
-    sram_id: u64,
+/// The bebe host script, as a transport.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Bebe;
+
+impl Transport for Bebe {
+    fn read(&mut self, addr: u64) -> io::Result<u64> {
+        bebe_read(addr, 8)
+    }
+
+    fn write(&mut self, addr: u64, data: u64) -> io::Result<()> {
+        bebe_write(addr, data, 8)
+    }
 }
 
-pub struct BebeScratchpadExecutor;
+/// The byte the chip's bootloader repeatedly sends while waiting for a host.
+pub const BEBE_BEACON: u8 = b'A';
+/// The magic sequence that connects to a waiting bootloader.
+pub const BEBE_NOCK_MAGIC: &[u8] = b"GOBEARS!";
+const BEBE_CMD_READV: u8 = b'R';
+const BEBE_CMD_WRITEV: u8 = b'W';
+const BEBE_CMD_ACK: u8 = b'Y';
 
-impl BebeExecutor {
-    pub fn new(sram_id: u64) -> Self {
-        Self { sram_id }
+/// A direct connection to the chip's bebe bootloader over its UART, speaking
+/// the same protocol as `bebe_host.py`.
+///
+/// Bytes are received on a background thread, so that every operation can
+/// time out even if the underlying reader blocks.
+pub struct BebeUart<W> {
+    tx: W,
+    rx: Receiver<u8>,
+    /// The timeout of each read or write.
+    timeout: Duration,
+}
+
+impl<W: Write> BebeUart<W> {
+    /// Connects over a UART whose received bytes come from `reader` and
+    /// whose transmitted bytes go to `writer`.
+    pub fn new<R: Read + Send + 'static>(mut reader: R, writer: W) -> Self {
+        let (sender, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut buf = [0; 64];
+            loop {
+                let n = match reader.read(&mut buf) {
+                    Ok(0) => return,
+                    Ok(n) => n,
+                    Err(err)
+                        if matches!(
+                            err.kind(),
+                            io::ErrorKind::TimedOut
+                                | io::ErrorKind::WouldBlock
+                                | io::ErrorKind::Interrupted
+                        ) =>
+                    {
+                        continue
+                    }
+                    Err(_) => return,
+                };
+                if buf[..n].iter().any(|&b| sender.send(b).is_err()) {
+                    return;
+                }
+            }
+        });
+        Self {
+            tx: writer,
+            rx,
+            timeout: Duration::from_secs(3),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Receives a byte, failing with [`io::ErrorKind::TimedOut`] if `deadline`
+    /// passes first.
+    fn recv(&mut self, deadline: Instant) -> io::Result<u8> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        self.rx.recv_timeout(timeout).map_err(|err| match err {
+            RecvTimeoutError::Timeout => {
+                io::Error::new(io::ErrorKind::TimedOut, "timed out waiting for bebe")
+            }
+            RecvTimeoutError::Disconnected => {
+                io::Error::new(io::ErrorKind::UnexpectedEof, "bebe UART closed")
+            }
+        })
+    }
+
+    fn send(&mut self, data: &[u8]) -> io::Result<()> {
+        self.tx.write_all(data)?;
+        self.tx.flush()
+    }
+
+    fn expect_ack(&mut self, deadline: Instant) -> io::Result<()> {
+        match self.recv(deadline)? {
+            BEBE_CMD_ACK => Ok(()),
+            b => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("expected ack from bebe, got {:?}", b as char),
+            )),
+        }
+    }
+
+    /// Waits for the bootloader's beacon.
+    pub fn wait_for_beacon(&mut self, timeout: Duration) -> io::Result<()> {
+        let deadline = Instant::now() + timeout;
+        while self.recv(deadline)? != BEBE_BEACON {}
+        Ok(())
+    }
+
+    /// Connects to the bootloader, which must be sending its beacon.
+    pub fn nock(&mut self, timeout: Duration) -> io::Result<()> {
+        let deadline = Instant::now() + timeout;
+        self.send(BEBE_NOCK_MAGIC)?;
+        loop {
+            // Beacons already in flight may arrive before the ack.
+            match self.recv(deadline)? {
+                BEBE_BEACON => continue,
+                BEBE_CMD_ACK => return Ok(()),
+                b => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unexpected response from bebe during nock: {:?}", b as char),
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Reads the 64-bit word at `addr`.
+    pub fn read_u64(&mut self, addr: u64) -> io::Result<u64> {
+        let deadline = Instant::now() + self.timeout;
+        let mut req = vec![BEBE_CMD_READV];
+        req.extend_from_slice(&8u32.to_be_bytes());
+        req.extend_from_slice(&addr.to_be_bytes());
+        self.send(&req)?;
+        let mut data = [0; 8];
+        for b in data.iter_mut() {
+            *b = self.recv(deadline)?;
+        }
+        Ok(u64::from_be_bytes(data))
+    }
+
+    /// Writes the 64-bit word at `addr`.
+    pub fn write_u64(&mut self, addr: u64, data: u64) -> io::Result<()> {
+        let deadline = Instant::now() + self.timeout;
+        let mut req = vec![BEBE_CMD_WRITEV];
+        req.extend_from_slice(&8u32.to_be_bytes());
+        req.extend_from_slice(&addr.to_be_bytes());
+        req.extend_from_slice(&data.to_be_bytes());
+        self.send(&req)?;
+        self.expect_ack(deadline)
     }
 }
 
-impl Executor for BebeExecutor {
-    fn init(&mut self) {}
-    fn read(&mut self, addr: SramAddr) -> SramWord {
-        bebe_write(0x1000, addr as u64, 8);
-        // no need to set the mask
-        // bebe_write(0x1010, u64::MAX, 8);
-        bebe_write(0x1018, 0, 8);
-        bebe_write(0x1020, self.sram_id, 8);
-        bebe_write(0x1028, 0, 8);
-        bebe_write(0x1038, 0, 8);
-        bebe_write(0x1180, u64::MAX, 8);
-        bebe_read(0x1040, 8)
+impl<W: Write> Transport for BebeUart<W> {
+    fn read(&mut self, addr: u64) -> io::Result<u64> {
+        self.read_u64(addr)
     }
 
-    fn write(&mut self, addr: SramAddr, data: SramWord, mask: SramWord) {
-        bebe_write(0x1000, addr as u64, 8);
-        bebe_write(0x1008, data, 8);
-        bebe_write(0x1010, mask, 8);
-        bebe_write(0x1018, u64::MAX, 8);
-        bebe_write(0x1020, self.sram_id, 8);
-        bebe_write(0x1028, 0, 8);
-        bebe_write(0x1038, 0, 8);
-        bebe_write(0x1180, u64::MAX, 8);
+    fn write(&mut self, addr: u64, data: u64) -> io::Result<()> {
+        self.write_u64(addr, data)
     }
+}
 
-    fn finish(&mut self) {}
+pub type BebeExecutor = MmioExecutor<Bebe>;
+
+impl BebeExecutor {
+    pub fn new(sram_id: u64) -> Self {
+        Self::with_driver(SramBist::new(Bebe), sram_id)
+    }
+}
+
+/// Runs whole patterns on the BIST engine, configured over bebe.
+#[cfg(feature = "unverified-bist")]
+pub type BebeBistExecutor = BistExecutor<Bebe>;
+
+#[cfg(feature = "unverified-bist")]
+impl BebeBistExecutor {
+    /// An executor for the STAC test SRAM with the given `SRAM_ID`.
+    pub fn new(sram_id: u64) -> Self {
+        Self::with_driver(SramBist::new(Bebe), sram_id)
+    }
 }
 
-const SCRATCHPAD_BASE_ADDR: u64 = 0x8000000;
+pub struct BebeScratchpadExecutor;
+
+/// The base address of the chip's scratchpad.
+pub const SCRATCHPAD_BASE_ADDR: u64 = 0x8000000;
 
 impl Executor for BebeScratchpadExecutor {
     fn init(&mut self) {}
     fn read(&mut self, addr: SramAddr) -> SramWord {
-        bebe_read(SCRATCHPAD_BASE_ADDR + addr as u64 * 8, 8)
+        bebe_read(SCRATCHPAD_BASE_ADDR + addr as u64 * 8, 8).expect("failed to read over bebe")
     }
 
     fn write(&mut self, addr: SramAddr, data: SramWord, mask: SramWord) {
         assert_eq!(mask, 0xFF, "scratchpad only supports mask of all 1s");
-        bebe_write(SCRATCHPAD_BASE_ADDR + addr as u64 * 8, data, 8);
+        bebe_write(SCRATCHPAD_BASE_ADDR + addr as u64 * 8, data, 8)
+            .expect("failed to write over bebe");
     }
 
     fn finish(&mut self) {}
@@ -0,0 +1,877 @@
+//! Compilation of [`Pattern`]s to the register encoding of the SramBist
+//! on-chip BIST engine, and decompilation of register images read back
+//! from the chip.
+//!
+//! Register widths follow `SramBistCtrlRegs` in the STAC controller. The
+//! element and op bit layout is described by the constants below, and all
+//! encoding and decoding goes through them.
+//!
+//! # Unverified encoding
+//!
+//! The SramBist RTL is not part of this repository, and the bit layout
+//! within `BIST_ELEMENT_SEQUENCE`, including the field offsets and op type
+//! encodings, is assumed rather than taken from it. Only the register widths
+//! have a source. In particular, 16 elements of 61 bits are assumed because
+//! they fill the 976-bit element sequence, although the 6-bit
+//! `BIST_MAX_ELEMENT_IDX` could index 64. Until the layout is checked
+//! against the RTL or vectors from its simulation, programs written with
+//! [`BistProgram::registers`] may not run as compiled. Wait elements are not
+//! supported, since nothing describes their encoding or the clock their
+//! cycle count is in, so patterns with pauses fail to compile with
+//! [`BistCompileError::UnsupportedPause`] until the RTL is available.
+//!
+//! This module, and everything that sends its output to the chip, is only
+//! built with the `unverified-bist` feature. It stays that way until the
+//! layout is taken from the RTL and tested against a known-good register
+//! image.
+
+use crate::pattern::{
+    AddrScope, AddrSeq, Element, Pattern, RandMask, SramAddr, SramInput, SramOp, SramSize, SramWord,
+};
+use crate::state::SramState;
+use crate::topology::{ColumnBits, SramTopology};
+use std::fmt;
+
+/// The number of elements in `BIST_ELEMENT_SEQUENCE`. Unverified: see the
+/// module documentation.
+pub const MAX_ELEMENTS: usize = 16;
+/// The number of op slots in each element.
+pub const MAX_OPS: usize = 5;
+/// The number of entries in `BIST_PATTERN_TABLE`.
+pub const PATTERN_TABLE_ENTRIES: usize = 8;
+/// The width of each `BIST_PATTERN_TABLE` entry, and the widest supported SRAM.
+pub const PATTERN_TABLE_WIDTH: u32 = 32;
+/// The largest supported number of rows (`BIST_MAX_ROW_ADDR` is 10 bits).
+pub const MAX_ROWS: u32 = 1 << 10;
+/// The largest supported mux ratio (`BIST_MAX_COL_ADDR` is 3 bits).
+pub const MAX_MUX: u32 = 1 << 3;
+
+/// The width of each element in `BIST_ELEMENT_SEQUENCE`.
+const ELEMENT_BITS: u32 = 61;
+const OP_BITS: u32 = 11;
+const MAX_OP_IDX_OFFSET: u32 = OP_BITS * MAX_OPS as u32;
+const DIR_OFFSET: u32 = MAX_OP_IDX_OFFSET + 3;
+const RAND_ADDR_OFFSET: u32 = DIR_OFFSET + 1;
+
+const OP_TYPE_OFFSET: u32 = 0;
+const DATA_SRC_OFFSET: u32 = 2;
+const MASK_SRC_OFFSET: u32 = 3;
+const DATA_IDX_OFFSET: u32 = 4;
+const MASK_IDX_OFFSET: u32 = 7;
+const INVERT_OFFSET: u32 = 10;
+
+const OP_READ: u64 = 0;
+const OP_WRITE: u64 = 1;
+const OP_RAND: u64 = 2;
+
+/// The number of 64-bit words in the `BIST_ELEMENT_SEQUENCE` register image.
+pub const ELEMENT_SEQUENCE_WORDS: usize = (MAX_ELEMENTS * ELEMENT_BITS as usize).div_ceil(64);
+/// The number of 64-bit words in the `BIST_PATTERN_TABLE` register image.
+pub const PATTERN_TABLE_WORDS: usize =
+    (PATTERN_TABLE_ENTRIES * PATTERN_TABLE_WIDTH as usize).div_ceil(64);
+
+/// Which address counter the BIST engine increments first.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum InnerDim {
+    /// Sweep the columns of each row (`BIST_INNER_DIM` = 0).
+    Col,
+    /// Sweep the rows of each column (`BIST_INNER_DIM` = 1).
+    Row,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum BistOpType {
+    /// Read and compare against the data source.
+    Read,
+    Write,
+    /// A random read or write of random data. Reads are not compared, but
+    /// are folded into `BIST_SIGNATURE`.
+    Rand,
+}
+
+/// Where an op takes its data or mask from.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum BistSource {
+    /// An entry of `BIST_PATTERN_TABLE`.
+    Table(u8),
+    /// The BIST random generator.
+    Rand,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct BistOp {
+    pub op_type: BistOpType,
+    pub data: BistSource,
+    pub mask: BistSource,
+    /// Whether to invert table data.
+    pub invert: bool,
+}
+
+/// A march element, which applies `ops` at every address.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct BistElement {
+    pub ops: Vec<BistOp>,
+    pub descending: bool,
+    /// Visit as many random addresses as there are addresses, instead of sweeping.
+    pub rand_addr: bool,
+}
+
+impl BistOp {
+    /// Whether the op takes its type, data or mask from the random generator.
+    pub fn is_random(&self) -> bool {
+        self.op_type == BistOpType::Rand
+            || self.data == BistSource::Rand
+            || self.mask == BistSource::Rand
+    }
+}
+
+impl BistElement {
+    /// Whether any address or op of the element comes from the random generator.
+    pub fn is_random(&self) -> bool {
+        self.rand_addr || self.ops.iter().any(BistOp::is_random)
+    }
+}
+
+/// A program for the BIST engine.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct BistProgram {
+    pub elements: Vec<BistElement>,
+    pub table: Vec<SramWord>,
+    pub max_row_addr: u32,
+    pub max_col_addr: u32,
+    pub inner_dim: InnerDim,
+}
+
+/// Register images of the BIST engine configuration.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct BistRegisters {
+    pub element_sequence: [u64; ELEMENT_SEQUENCE_WORDS],
+    pub pattern_table: [u64; PATTERN_TABLE_WORDS],
+    pub max_element_idx: u64,
+    pub max_row_addr: u64,
+    pub max_col_addr: u64,
+    pub inner_dim: u64,
+}
+
+/// Run-time settings of the BIST engine.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct BistRunConfig {
+    /// The seed of the random generator (`BIST_RAND_SEED`, 78 bits).
+    pub rand_seed: u128,
+    /// The initial signature (`BIST_SIG_SEED`).
+    pub sig_seed: u32,
+    /// The cycle after which the engine stops (`BIST_CYCLE_LIMIT`).
+    pub cycle_limit: u32,
+    /// Whether to stop at the first failing read (`BIST_STOP_ON_FAILURE`).
+    pub stop_on_failure: bool,
+}
+
+/// The width of `BIST_RAND_SEED`.
+pub const RAND_SEED_BITS: u32 = 78;
+
+impl Default for BistRunConfig {
+    fn default() -> Self {
+        Self {
+            rand_seed: 1,
+            sig_seed: 0,
+            cycle_limit: u32::MAX,
+            stop_on_failure: true,
+        }
+    }
+}
+
+/// The results of a BIST run, read back from the engine.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct BistOutcome {
+    /// Whether any read failed (`BIST_FAIL`).
+    pub fail: bool,
+    /// The cycle of the first failing read (`BIST_FAIL_CYCLE`).
+    pub fail_cycle: u32,
+    /// The expected and received data of the first failing read.
+    pub expected: SramWord,
+    pub received: SramWord,
+    /// The compacted read data (`BIST_SIGNATURE`).
+    pub signature: u32,
+}
+
+/// The op executing in a given cycle of a [`BistProgram`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct BistLocation {
+    pub element: usize,
+    /// The index of the op among the ops of [`BistProgram::pattern`].
+    pub op: usize,
+    /// The address accessed, if known. Addresses visited by random address
+    /// elements depend on the random generator, and are given by
+    /// [`simulate`](crate::golden::simulate).
+    pub addr: Option<SramAddr>,
+}
+
+/// An error compiling a [`Pattern`] for the BIST engine.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum BistCompileError {
+    /// The SRAM is wider than the pattern table, or has more rows or columns
+    /// than the address counters.
+    UnsupportedSram {
+        size: SramSize,
+        mux: u32,
+    },
+    /// The BIST address counters assume the column select is in the low address bits.
+    UnsupportedColumnBits,
+    TooManyElements {
+        elements: usize,
+    },
+    EmptyElement {
+        element: usize,
+    },
+    TooManyOps {
+        element: usize,
+        ops: usize,
+    },
+    /// The pattern uses more distinct data words and masks than fit in the pattern table.
+    PatternTableFull {
+        element: usize,
+    },
+    UnsupportedAddrSeq {
+        element: usize,
+        addr_seq: AddrSeq,
+    },
+    /// Elements can only address the whole SRAM.
+    UnsupportedScope {
+        element: usize,
+    },
+    UnsupportedOp {
+        element: usize,
+        op: SramOp,
+    },
+    UnsupportedInput {
+        element: usize,
+        input: SramInput,
+    },
+    /// Random address elements always visit as many addresses as the SRAM has.
+    UnsupportedRandCount {
+        element: usize,
+        n: u64,
+    },
+    /// Row-fast and column-fast elements cannot be mixed, since `BIST_INNER_DIM` is global.
+    MixedInnerDim {
+        element: usize,
+    },
+    /// A read whose expected value is not known at compile time, e.g. after random writes.
+    UnknownReadData {
+        element: usize,
+    },
+    /// Pauses need wait elements, whose encoding is not known.
+    ///
+    /// Translating a pause into a wait cycle count is blocked on the RTL:
+    /// the width of the count field and the clock it counts are not known
+    /// either. Until then, pauses are rejected rather than guessed.
+    UnsupportedPause {
+        element: usize,
+    },
+}
+
+impl fmt::Display for BistCompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BistCompileError::UnsupportedSram { size, mux } => write!(
+                f,
+                "{}x{} SRAM with mux {mux} exceeds BIST limits (width <= {PATTERN_TABLE_WIDTH}, rows <= {MAX_ROWS}, mux <= {MAX_MUX})",
+                size.depth(),
+                size.width()
+            ),
+            BistCompileError::UnsupportedColumnBits => {
+                write!(f, "BIST requires column select in the low address bits")
+            }
+            BistCompileError::TooManyElements { elements } => write!(
+                f,
+                "pattern has {elements} elements, but BIST supports at most {MAX_ELEMENTS}"
+            ),
+            BistCompileError::EmptyElement { element } => {
+                write!(f, "element {element} has no operations")
+            }
+            BistCompileError::TooManyOps { element, ops } => write!(
+                f,
+                "element {element} has {ops} operations, but BIST supports at most {MAX_OPS}"
+            ),
+            BistCompileError::PatternTableFull { element } => write!(
+                f,
+                "element {element} needs more than {PATTERN_TABLE_ENTRIES} distinct pattern table entries"
+            ),
+            BistCompileError::UnsupportedAddrSeq { element, addr_seq } => write!(
+                f,
+                "element {element} uses address sequence {addr_seq:?}, which BIST does not support"
+            ),
+            BistCompileError::UnsupportedScope { element } => write!(
+                f,
+                "element {element} does not address the whole SRAM"
+            ),
+            BistCompileError::UnsupportedOp { element, op } => write!(
+                f,
+                "element {element} uses operation {op:?}, which BIST does not support"
+            ),
+            BistCompileError::UnsupportedInput { element, input } => write!(
+                f,
+                "element {element} uses input {input:?}, which BIST does not support"
+            ),
+            BistCompileError::UnsupportedRandCount { element, n } => write!(
+                f,
+                "element {element} visits {n} random addresses, but BIST always visits one per address"
+            ),
+            BistCompileError::MixedInnerDim { element } => write!(
+                f,
+                "element {element} sweeps a different inner dimension than previous elements"
+            ),
+            BistCompileError::UnknownReadData { element } => write!(
+                f,
+                "element {element} reads data that is not known at compile time"
+            ),
+            BistCompileError::UnsupportedPause { element } => write!(
+                f,
+                "element {element} pauses, but BIST wait elements are not supported until their encoding is known"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BistCompileError {}
+
+/// An error decoding BIST register images.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum BistDecodeError {
+    InvalidMaxElementIdx {
+        max_element_idx: u64,
+    },
+    InvalidMaxOpIdx {
+        element: usize,
+        max_op_idx: u64,
+    },
+    InvalidOpType {
+        element: usize,
+        op: usize,
+        op_type: u64,
+    },
+}
+
+impl fmt::Display for BistDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BistDecodeError::InvalidMaxElementIdx { max_element_idx } => write!(
+                f,
+                "max element index {max_element_idx} exceeds {}",
+                MAX_ELEMENTS - 1
+            ),
+            BistDecodeError::InvalidMaxOpIdx {
+                element,
+                max_op_idx,
+            } => write!(
+                f,
+                "element {element} has max op index {max_op_idx}, which exceeds {}",
+                MAX_OPS - 1
+            ),
+            BistDecodeError::InvalidOpType {
+                element,
+                op,
+                op_type,
+            } => write!(f, "op {op} of element {element} has invalid type {op_type}"),
+        }
+    }
+}
+
+impl std::error::Error for BistDecodeError {}
+
+/// Compiles `pattern` for the BIST engine testing an SRAM with the given topology.
+///
+/// The encoding is unverified; see the module documentation.
+pub fn compile(
+    pattern: &Pattern,
+    topology: &SramTopology,
+) -> Result<BistProgram, BistCompileError> {
+    let size = topology.size();
+    if size.width() > PATTERN_TABLE_WIDTH as SramWord
+        || topology.rows() > MAX_ROWS
+        || topology.mux() > MAX_MUX
+    {
+        return Err(BistCompileError::UnsupportedSram {
+            size,
+            mux: topology.mux(),
+        });
+    }
+    if topology.column_bits() != ColumnBits::Low {
+        return Err(BistCompileError::UnsupportedColumnBits);
+    }
+    if pattern.elements().len() > MAX_ELEMENTS {
+        return Err(BistCompileError::TooManyElements {
+            elements: pattern.elements().len(),
+        });
+    }
+
+    let mut compiler = Compiler {
+        size,
+        table: Vec::new(),
+        // Tracks the value held by every address, which is uniform since
+        // every element addresses the whole SRAM.
+        state: SramState::new(SramSize::new(size.width(), 1, size.mask_width())),
+        element: 0,
+    };
+    let mut inner_dim = None;
+    let mut elements = Vec::new();
+    for (element, elt) in pattern.elements().iter().enumerate() {
+        compiler.element = element;
+        if elt.ops().is_empty() {
+            return Err(BistCompileError::EmptyElement { element });
+        }
+        if *elt.scope() != AddrScope::Full {
+            return Err(BistCompileError::UnsupportedScope { element });
+        }
+        if elt.is_pause() {
+            return Err(BistCompileError::UnsupportedPause { element });
+        }
+        if elt.ops().len() > MAX_OPS {
+            return Err(BistCompileError::TooManyOps {
+                element,
+                ops: elt.ops().len(),
+            });
+        }
+
+        let (dim, descending, rand_addr) = match elt.addr_seq() {
+            AddrSeq::Up => (Some(InnerDim::Col), false, false),
+            AddrSeq::Down => (Some(InnerDim::Col), true, false),
+            AddrSeq::ColFast { descending } => (Some(InnerDim::Col), descending, false),
+            AddrSeq::RowFast { descending } => (Some(InnerDim::Row), descending, false),
+            AddrSeq::Rand(n) => {
+                if n != size.depth() as u64 {
+                    return Err(BistCompileError::UnsupportedRandCount { element, n });
+                }
+                (None, false, true)
+            }
+            addr_seq => {
+                return Err(BistCompileError::UnsupportedAddrSeq { element, addr_seq });
+            }
+        };
+        if let Some(dim) = dim {
+            if *inner_dim.get_or_insert(dim) != dim {
+                return Err(BistCompileError::MixedInnerDim { element });
+            }
+        }
+
+        let ops = elt
+            .ops()
+            .iter()
+            .map(|op| compiler.op(op))
+            .collect::<Result<Vec<_>, _>>()?;
+        elements.push(BistElement {
+            ops,
+            descending,
+            rand_addr,
+        });
+    }
+
+    Ok(BistProgram {
+        elements,
+        table: compiler.table,
+        max_row_addr: topology.rows() - 1,
+        max_col_addr: topology.mux() - 1,
+        inner_dim: inner_dim.unwrap_or(InnerDim::Col),
+    })
+}
+
+struct Compiler {
+    size: SramSize,
+    table: Vec<SramWord>,
+    state: SramState,
+    element: usize,
+}
+
+impl Compiler {
+    fn op(&mut self, op: &SramOp) -> Result<BistOp, BistCompileError> {
+        let element = self.element;
+        let dmask = u64::MAX >> (64 - self.size.width());
+        let mask_mask = u64::MAX >> (64 - self.size.mask_width());
+        Ok(match *op {
+            SramOp::Read => {
+                let expected = self
+                    .state
+                    .read(0)
+                    .ok_or(BistCompileError::UnknownReadData { element })?;
+                let (data, invert) = self.data(expected)?;
+                BistOp {
+                    op_type: BistOpType::Read,
+                    data,
+                    mask: BistSource::Table(0),
+                    invert,
+                }
+            }
+            SramOp::Write { data, mask } => {
+                let mask_value = match mask {
+                    SramInput::Fixed(mask) => Some(mask & mask_mask),
+                    SramInput::Rand => None,
+                    input => return Err(BistCompileError::UnsupportedInput { element, input }),
+                };
+                let mask = match mask_value {
+                    Some(mask) => BistSource::Table(self.entry(mask)?),
+                    None => BistSource::Rand,
+                };
+                let (data, invert) = match data {
+                    SramInput::Fixed(data) => {
+                        let data = data & dmask;
+                        match mask_value {
+                            Some(mask) => self.state.write(0, data, mask),
+                            None => self.forget(),
+                        }
+                        self.data(data)?
+                    }
+                    SramInput::Rand => {
+                        self.forget();
+                        (BistSource::Rand, false)
+                    }
+                    input => return Err(BistCompileError::UnsupportedInput { element, input }),
+                };
+                BistOp {
+                    op_type: BistOpType::Write,
+                    data,
+                    mask,
+                    invert,
+                }
+            }
+            SramOp::Rand { mask } => {
+                let mask = match mask {
+                    RandMask::Fixed(mask) => BistSource::Table(self.entry(mask & mask_mask)?),
+                    RandMask::Rand => BistSource::Rand,
+                    RandMask::SingleLane => {
+                        return Err(BistCompileError::UnsupportedOp { element, op: *op })
+                    }
+                };
+                self.forget();
+                BistOp {
+                    op_type: BistOpType::Rand,
+                    data: BistSource::Rand,
+                    mask,
+                    invert: false,
+                }
+            }
+            op => return Err(BistCompileError::UnsupportedOp { element, op }),
+        })
+    }
+
+    /// Forgets the tracked contents of the SRAM.
+    fn forget(&mut self) {
+        self.state = SramState::new(SramSize::new(self.size.width(), 1, self.size.mask_width()));
+    }
+
+    /// The pattern table source for `word`, reusing an entry holding its inverse if possible.
+    fn data(&mut self, word: SramWord) -> Result<(BistSource, bool), BistCompileError> {
+        let dmask = u64::MAX >> (64 - self.size.width());
+        if let Some(idx) = self.table.iter().position(|&entry| entry == !word & dmask) {
+            if !self.table.contains(&word) {
+                return Ok((BistSource::Table(idx as u8), true));
+            }
+        }
+        Ok((BistSource::Table(self.entry(word)?), false))
+    }
+
+    /// The index of the pattern table entry holding `word`, adding it if necessary.
+    fn entry(&mut self, word: SramWord) -> Result<u8, BistCompileError> {
+        if let Some(idx) = self.table.iter().position(|&entry| entry == word) {
+            return Ok(idx as u8);
+        }
+        if self.table.len() == PATTERN_TABLE_ENTRIES {
+            return Err(BistCompileError::PatternTableFull {
+                element: self.element,
+            });
+        }
+        self.table.push(word);
+        Ok((self.table.len() - 1) as u8)
+    }
+}
+
+/// Decompiles BIST register images into a [`Pattern`].
+pub fn decompile(regs: &BistRegisters) -> Result<Pattern, BistDecodeError> {
+    Ok(BistProgram::decode(regs)?.pattern())
+}
+
+impl BistProgram {
+    /// The number of addresses swept by each march element.
+    pub fn depth(&self) -> u32 {
+        (self.max_row_addr + 1) * (self.max_col_addr + 1)
+    }
+
+    /// The index of the first element taking addresses, data or masks from the
+    /// BIST random generator, if any.
+    pub fn random_element(&self) -> Option<usize> {
+        self.elements.iter().position(BistElement::is_random)
+    }
+
+    /// The number of cycles the program takes: one per op per address.
+    pub fn cycles(&self) -> u64 {
+        self.elements
+            .iter()
+            .map(|elt| elt.ops.len() as u64 * self.depth() as u64)
+            .sum()
+    }
+
+    /// Locates the op executing in the given cycle, counted from 0.
+    pub fn locate(&self, topology: &SramTopology, cycle: u64) -> Option<BistLocation> {
+        assert_eq!(
+            (topology.rows() - 1, topology.mux() - 1),
+            (self.max_row_addr, self.max_col_addr),
+            "topology does not match program"
+        );
+        let depth = self.depth() as u64;
+        let (mut start, mut op) = (0, 0);
+        for (element, elt) in self.elements.iter().enumerate() {
+            let cycles = elt.ops.len() as u64 * depth;
+            if cycle < start + cycles {
+                let offset = cycle - start;
+                let mut i = (offset / elt.ops.len() as u64) as u32;
+                if elt.descending {
+                    i = self.depth() - 1 - i;
+                }
+                let addr = match self.inner_dim {
+                    InnerDim::Col => topology.addr(i / topology.mux(), i % topology.mux()),
+                    InnerDim::Row => topology.addr(i % topology.rows(), i / topology.rows()),
+                };
+                return Some(BistLocation {
+                    element,
+                    op: op + offset as usize,
+                    addr: (!elt.rand_addr).then_some(addr),
+                });
+            }
+            start += cycles;
+            op += cycles as usize;
+        }
+        None
+    }
+
+    /// The pattern run by this program.
+    pub fn pattern(&self) -> Pattern {
+        let table = |source: BistSource, invert: bool| -> SramInput {
+            match source {
+                BistSource::Table(idx) => {
+                    let word = self.table[idx as usize];
+                    SramInput::Fixed(if invert { !word } else { word })
+                }
+                BistSource::Rand => SramInput::Rand,
+            }
+        };
+        let elements = self.elements.iter().map(|elt| {
+            let addr_seq = match (elt.rand_addr, self.inner_dim, elt.descending) {
+                (true, _, _) => AddrSeq::Rand(self.depth() as u64),
+                (false, InnerDim::Col, false) => AddrSeq::Up,
+                (false, InnerDim::Col, true) => AddrSeq::Down,
+                (false, InnerDim::Row, descending) => AddrSeq::RowFast { descending },
+            };
+            let ops = elt.ops.iter().map(|op| match op.op_type {
+                BistOpType::Read => SramOp::Read,
+                BistOpType::Write => SramOp::Write {
+                    data: table(op.data, op.invert),
+                    mask: table(op.mask, false),
+                },
+                BistOpType::Rand => SramOp::Rand {
+                    mask: match table(op.mask, false) {
+                        SramInput::Fixed(mask) => RandMask::Fixed(mask),
+                        _ => RandMask::Rand,
+                    },
+                },
+            });
+            Element::new(addr_seq, ops)
+        });
+        elements
+            .fold(Pattern::builder(), |builder, elt| builder.element(elt))
+            .build()
+    }
+
+    /// Encodes this program as register images.
+    ///
+    /// The encoding is unverified; see the module documentation.
+    pub fn registers(&self) -> BistRegisters {
+        assert!(
+            !self.elements.is_empty() && self.elements.len() <= MAX_ELEMENTS,
+            "invalid number of BIST elements"
+        );
+        assert!(
+            self.table.len() <= PATTERN_TABLE_ENTRIES,
+            "too many pattern table entries"
+        );
+        let mut element_sequence = [0; ELEMENT_SEQUENCE_WORDS];
+        for (i, elt) in self.elements.iter().enumerate() {
+            set_bits(
+                &mut element_sequence,
+                i as u32 * ELEMENT_BITS,
+                ELEMENT_BITS,
+                encode_element(elt),
+            );
+        }
+        let mut pattern_table = [0; PATTERN_TABLE_WORDS];
+        for (i, &entry) in self.table.iter().enumerate() {
+            set_bits(
+                &mut pattern_table,
+                i as u32 * PATTERN_TABLE_WIDTH,
+                PATTERN_TABLE_WIDTH,
+                entry,
+            );
+        }
+        BistRegisters {
+            element_sequence,
+            pattern_table,
+            max_element_idx: self.elements.len() as u64 - 1,
+            max_row_addr: self.max_row_addr as u64,
+            max_col_addr: self.max_col_addr as u64,
+            inner_dim: match self.inner_dim {
+                InnerDim::Col => 0,
+                InnerDim::Row => 1,
+            },
+        }
+    }
+
+    /// Decodes register images read back from the chip.
+    ///
+    /// All pattern table entries are decoded, since the registers do not
+    /// record how many are in use.
+    pub fn decode(regs: &BistRegisters) -> Result<Self, BistDecodeError> {
+        if regs.max_element_idx as usize >= MAX_ELEMENTS {
+            return Err(BistDecodeError::InvalidMaxElementIdx {
+                max_element_idx: regs.max_element_idx,
+            });
+        }
+        let elements = (0..=regs.max_element_idx as usize)
+            .map(|i| {
+                decode_element(
+                    i,
+                    get_bits(
+                        &regs.element_sequence,
+                        i as u32 * ELEMENT_BITS,
+                        ELEMENT_BITS,
+                    ),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let table = (0..PATTERN_TABLE_ENTRIES as u32)
+            .map(|i| {
+                get_bits(
+                    &regs.pattern_table,
+                    i * PATTERN_TABLE_WIDTH,
+                    PATTERN_TABLE_WIDTH,
+                )
+            })
+            .collect();
+        Ok(Self {
+            elements,
+            table,
+            max_row_addr: regs.max_row_addr as u32,
+            max_col_addr: regs.max_col_addr as u32,
+            inner_dim: if regs.inner_dim & 1 == 0 {
+                InnerDim::Col
+            } else {
+                InnerDim::Row
+            },
+        })
+    }
+}
+
+fn encode_element(elt: &BistElement) -> u64 {
+    assert!(
+        !elt.ops.is_empty() && elt.ops.len() <= MAX_OPS,
+        "invalid number of BIST ops"
+    );
+    let mut word = elt.ops.iter().enumerate().fold(0, |word, (i, op)| {
+        word | encode_op(op) << (i as u32 * OP_BITS)
+    });
+    word |= (elt.ops.len() as u64 - 1) << MAX_OP_IDX_OFFSET;
+    word |= (elt.descending as u64) << DIR_OFFSET;
+    word |= (elt.rand_addr as u64) << RAND_ADDR_OFFSET;
+    word
+}
+
+fn encode_op(op: &BistOp) -> u64 {
+    let source = |source: BistSource| match source {
+        BistSource::Table(idx) => {
+            assert!(
+                (idx as usize) < PATTERN_TABLE_ENTRIES,
+                "invalid table index"
+            );
+            (0, idx as u64)
+        }
+        BistSource::Rand => (1, 0),
+    };
+    let op_type = match op.op_type {
+        BistOpType::Read => OP_READ,
+        BistOpType::Write => OP_WRITE,
+        BistOpType::Rand => OP_RAND,
+    };
+    let (data_src, data_idx) = source(op.data);
+    let (mask_src, mask_idx) = source(op.mask);
+    op_type << OP_TYPE_OFFSET
+        | data_src << DATA_SRC_OFFSET
+        | mask_src << MASK_SRC_OFFSET
+        | data_idx << DATA_IDX_OFFSET
+        | mask_idx << MASK_IDX_OFFSET
+        | (op.invert as u64) << INVERT_OFFSET
+}
+
+fn decode_element(element: usize, word: u64) -> Result<BistElement, BistDecodeError> {
+    let max_op_idx = word >> MAX_OP_IDX_OFFSET & 0b111;
+    if max_op_idx as usize >= MAX_OPS {
+        return Err(BistDecodeError::InvalidMaxOpIdx {
+            element,
+            max_op_idx,
+        });
+    }
+    let ops = (0..=max_op_idx as usize)
+        .map(|op| decode_op(element, op, word >> (op as u32 * OP_BITS)))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(BistElement {
+        ops,
+        descending: word >> DIR_OFFSET & 1 != 0,
+        rand_addr: word >> RAND_ADDR_OFFSET & 1 != 0,
+    })
+}
+
+fn decode_op(element: usize, op: usize, word: u64) -> Result<BistOp, BistDecodeError> {
+    let field = |offset: u32, bits: u32| word >> offset & ((1 << bits) - 1);
+    let source = |src: u64, idx: u64| {
+        if src == 0 {
+            BistSource::Table(idx as u8)
+        } else {
+            BistSource::Rand
+        }
+    };
+    let op_type = match field(OP_TYPE_OFFSET, 2) {
+        OP_READ => BistOpType::Read,
+        OP_WRITE => BistOpType::Write,
+        OP_RAND => BistOpType::Rand,
+        op_type => {
+            return Err(BistDecodeError::InvalidOpType {
+                element,
+                op,
+                op_type,
+            })
+        }
+    };
+    Ok(BistOp {
+        op_type,
+        data: source(field(DATA_SRC_OFFSET, 1), field(DATA_IDX_OFFSET, 3)),
+        mask: source(field(MASK_SRC_OFFSET, 1), field(MASK_IDX_OFFSET, 3)),
+        invert: field(INVERT_OFFSET, 1) != 0,
+    })
+}
+
+/// Writes the low `width` bits of `value` at bit `offset` of a little-endian register image.
+fn set_bits(words: &mut [u64], offset: u32, width: u32, value: u64) {
+    for bit in 0..width {
+        let pos = (offset + bit) as usize;
+        let (word, bit_in_word) = (pos / 64, pos % 64);
+        if value >> bit & 1 != 0 {
+            words[word] |= 1 << bit_in_word;
+        } else {
+            words[word] &= !(1 << bit_in_word);
+        }
+    }
+}
+
+/// Reads `width` bits at bit `offset` of a little-endian register image.
+fn get_bits(words: &[u64], offset: u32, width: u32) -> u64 {
+    (0..width).fold(0, |value, bit| {
+        let pos = (offset + bit) as usize;
+        value | (words[pos / 64] >> (pos % 64) & 1) << bit
+    })
+}
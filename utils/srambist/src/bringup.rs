@@ -0,0 +1,395 @@
+//! Scripted power-up of a STAC board, with a health check and diagnosis at
+//! every step.
+//!
+//! Bring-up is a state machine over the steps of a [`BringupSequence`], each
+//! with its own timeout. The sequence depends on the board revision, or can
+//! be read from a sequence file for boards without one.
+
+use crate::bebe::{BebeUart, SCRATCHPAD_BASE_ADDR};
+use crate::regs::sram_bist;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tsi::stac::{half_clk_div_ratio, SramMode, StacController};
+
+/// A step of bring-up.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Step {
+    /// Gates the chip clock off and returns the SRAMs to internal control.
+    Reset,
+    /// Sets the clock divider for the frequency nearest to the given one.
+    SetClock {
+        hz: u64,
+    },
+    EnableClock,
+    /// Reads back the controller registers written by earlier steps.
+    CheckController,
+    /// Resets the PLL, holding the resets low for the given duration.
+    PllReset {
+        hold: Duration,
+    },
+    /// Waits for the bootloader's UART beacon.
+    WaitBeacon,
+    /// Connects to the bootloader.
+    Nock,
+    /// Writes and reads back the given number of scratchpad words.
+    CheckScratchpad {
+        words: u64,
+    },
+    /// Writes and reads back an SramBist register.
+    CheckSramBist,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Reset => write!(f, "reset controller"),
+            Step::SetClock { hz } => write!(f, "set clock to {hz} Hz"),
+            Step::EnableClock => write!(f, "enable clock"),
+            Step::CheckController => write!(f, "check controller"),
+            Step::PllReset { .. } => write!(f, "reset PLL"),
+            Step::WaitBeacon => write!(f, "wait for beacon"),
+            Step::Nock => write!(f, "nock"),
+            Step::CheckScratchpad { .. } => write!(f, "check scratchpad"),
+            Step::CheckSramBist => write!(f, "check SramBist registers"),
+        }
+    }
+}
+
+/// The steps of bring-up, each with its timeout.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct BringupSequence {
+    pub steps: Vec<(Step, Duration)>,
+}
+
+/// Parses a sequence file, with one step per line:
+///
+/// ```text
+/// # STAC-V1 at 400 kHz
+/// reset 1000
+/// set-clock 400000 1000
+/// enable-clock 1000
+/// pll-reset 10 1000
+/// wait-beacon 5000
+/// ```
+///
+/// Each line names a step, then its parameter if it has one (the frequency
+/// in Hz of `set-clock`, the hold time in ms of `pll-reset`, or the word
+/// count of `check-scratchpad`), then its timeout in ms. The other steps are
+/// `check-controller`, `nock` and `check-sram-bist`. Blank lines and text
+/// after `#` are ignored.
+impl FromStr for BringupSequence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut steps = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let Some((&name, args)) = fields.split_first() else {
+                continue;
+            };
+            let err = |msg: &str| format!("line {}: {msg}: {}", i + 1, line.trim());
+            let num = |field: &str| {
+                field
+                    .parse::<u64>()
+                    .map_err(|_| err(&format!("{field:?} is not a number")))
+            };
+            let (param, timeout) = match args {
+                [timeout] => (None, num(timeout)?),
+                [param, timeout] => (Some(num(param)?), num(timeout)?),
+                _ => return Err(err("expected a step, an optional parameter and a timeout")),
+            };
+            let step = match (name, param) {
+                ("reset", None) => Step::Reset,
+                ("set-clock", Some(hz)) => Step::SetClock { hz },
+                ("enable-clock", None) => Step::EnableClock,
+                ("check-controller", None) => Step::CheckController,
+                ("pll-reset", Some(hold)) => Step::PllReset {
+                    hold: Duration::from_millis(hold),
+                },
+                ("wait-beacon", None) => Step::WaitBeacon,
+                ("nock", None) => Step::Nock,
+                ("check-scratchpad", Some(words)) => Step::CheckScratchpad { words },
+                ("check-sram-bist", None) => Step::CheckSramBist,
+                (
+                    "reset" | "enable-clock" | "check-controller" | "wait-beacon" | "nock"
+                    | "check-sram-bist",
+                    Some(_),
+                ) => return Err(err(&format!("{name} takes no parameter"))),
+                ("set-clock" | "pll-reset" | "check-scratchpad", None) => {
+                    return Err(err(&format!("{name} needs a parameter")))
+                }
+                _ => return Err(err(&format!("unknown step {name:?}"))),
+            };
+            steps.push((step, Duration::from_millis(timeout)));
+        }
+        Ok(Self { steps })
+    }
+}
+
+/// A revision of the STAC board, which determines its bring-up sequence.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum BoardRevision {
+    /// The STAC-V1 board, clocked directly by the FPGA divider.
+    StacV1,
+}
+
+impl BoardRevision {
+    pub fn sequence(&self) -> BringupSequence {
+        let s = Duration::from_secs;
+        match self {
+            BoardRevision::StacV1 => BringupSequence {
+                steps: vec![
+                    (Step::Reset, s(1)),
+                    (Step::SetClock { hz: 200_000 }, s(1)),
+                    (Step::EnableClock, s(1)),
+                    (Step::CheckController, s(1)),
+                    (Step::WaitBeacon, s(5)),
+                    (Step::Nock, s(3)),
+                    (Step::CheckScratchpad { words: 4 }, s(10)),
+                    (Step::CheckSramBist, s(10)),
+                ],
+            },
+        }
+    }
+}
+
+impl FromStr for BoardRevision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stac-v1" => Ok(BoardRevision::StacV1),
+            _ => Err(format!("unknown board revision {s:?}, expected stac-v1")),
+        }
+    }
+}
+
+/// A failed bring-up step, with a diagnosis of the likely cause.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct BringupError {
+    pub step: Step,
+    pub diagnosis: String,
+}
+
+impl fmt::Display for BringupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} failed: {}", self.step, self.diagnosis)
+    }
+}
+
+impl std::error::Error for BringupError {}
+
+/// The progress of bring-up through a sequence.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum BringupState {
+    /// About to run the step at the given index.
+    Pending(usize),
+    /// Every step passed.
+    Ready,
+    Failed(BringupError),
+}
+
+/// Brings up a STAC board through the FPGA's `StacController` and the
+/// chip's bebe UART.
+///
+/// Reads from the controller should fail with [`io::ErrorKind::TimedOut`]
+/// within the step timeouts, which is diagnosed as a silent FPGA. Otherwise
+/// a step can block forever.
+pub struct Bringup<T, W> {
+    controller: StacController<T>,
+    uart: BebeUart<W>,
+    sequence: BringupSequence,
+    state: BringupState,
+    /// The clock divider and enable written so far, to check against.
+    expected_ratio: Option<u32>,
+    expected_clk_en: Option<bool>,
+}
+
+impl<T: Read + Write, W: Write> Bringup<T, W> {
+    pub fn new(
+        controller: StacController<T>,
+        uart: BebeUart<W>,
+        sequence: BringupSequence,
+    ) -> Self {
+        let state = if sequence.steps.is_empty() {
+            BringupState::Ready
+        } else {
+            BringupState::Pending(0)
+        };
+        Self {
+            controller,
+            uart,
+            sequence,
+            state,
+            expected_ratio: None,
+            expected_clk_en: None,
+        }
+    }
+
+    pub fn state(&self) -> &BringupState {
+        &self.state
+    }
+
+    pub fn into_inner(self) -> (StacController<T>, BebeUart<W>) {
+        (self.controller, self.uart)
+    }
+
+    /// Runs the next step, if any, and returns the new state.
+    pub fn advance(&mut self) -> &BringupState {
+        if let BringupState::Pending(idx) = self.state {
+            let (step, timeout) = self.sequence.steps[idx];
+            let start = Instant::now();
+            let result = self.run_step(step, timeout).and_then(|()| {
+                let elapsed = start.elapsed();
+                if elapsed > timeout {
+                    Err(format!(
+                        "took {:.1} s, longer than the {:.1} s timeout",
+                        elapsed.as_secs_f64(),
+                        timeout.as_secs_f64()
+                    ))
+                } else {
+                    Ok(())
+                }
+            });
+            self.state = match result {
+                Ok(()) if idx + 1 == self.sequence.steps.len() => BringupState::Ready,
+                Ok(()) => BringupState::Pending(idx + 1),
+                Err(diagnosis) => BringupState::Failed(BringupError { step, diagnosis }),
+            };
+        }
+        &self.state
+    }
+
+    /// Runs every remaining step, stopping at the first failure.
+    pub fn run(&mut self) -> Result<(), BringupError> {
+        loop {
+            if let BringupState::Pending(idx) = self.state {
+                print!("[bringup] {}... ", self.sequence.steps[idx].0);
+                io::stdout().flush().ok();
+            }
+            match self.advance() {
+                BringupState::Pending(_) => println!("ok"),
+                BringupState::Ready => {
+                    println!("ok");
+                    return Ok(());
+                }
+                BringupState::Failed(err) => {
+                    println!("FAILED");
+                    return Err(err.clone());
+                }
+            }
+        }
+    }
+
+    /// Runs `step`, returning a diagnosis if it fails.
+    fn run_step(&mut self, step: Step, timeout: Duration) -> Result<(), String> {
+        let fpga = |err: io::Error| match err.kind() {
+            io::ErrorKind::TimedOut => {
+                "no response from FPGA: check the TTY, baud rate and bitstream".to_string()
+            }
+            _ => format!("FPGA TTY error ({err}): check the TTY"),
+        };
+        match step {
+            Step::Reset => {
+                self.controller.enable_clock(false).map_err(fpga)?;
+                self.controller
+                    .set_sram_mode(SramMode::Internal)
+                    .map_err(fpga)?;
+                self.controller.bist_stop().map_err(fpga)?;
+                self.expected_clk_en = Some(false);
+            }
+            Step::SetClock { hz } => {
+                self.controller.set_clock_hz(hz).map_err(fpga)?;
+                self.expected_ratio = Some(half_clk_div_ratio(hz));
+            }
+            Step::EnableClock => {
+                self.controller.enable_clock(true).map_err(fpga)?;
+                self.expected_clk_en = Some(true);
+            }
+            Step::CheckController => {
+                let status = self.controller.status().map_err(fpga)?;
+                if let Some(ratio) = self.expected_ratio {
+                    if status.half_clk_div_ratio != ratio {
+                        return Err(format!(
+                            "HALF_CLK_DIV_RATIO reads {}, expected {ratio}: check the FPGA bitstream",
+                            status.half_clk_div_ratio
+                        ));
+                    }
+                }
+                if let Some(clk_en) = self.expected_clk_en {
+                    if status.clk_en != clk_en {
+                        return Err(format!(
+                            "CLK_EN reads {}, expected {}: check the FPGA bitstream",
+                            status.clk_en as u8, clk_en as u8
+                        ));
+                    }
+                }
+            }
+            Step::PllReset { hold } => {
+                self.controller.pll_reset_sequence(hold).map_err(fpga)?;
+            }
+            Step::WaitBeacon => {
+                if let Err(err) = self.uart.wait_for_beacon(timeout) {
+                    let clk_en = self.controller.status().map(|status| status.clk_en);
+                    return Err(match (err.kind(), clk_en) {
+                        (io::ErrorKind::TimedOut, Ok(false)) => {
+                            "no beacon: CLK_EN is not set".to_string()
+                        }
+                        (io::ErrorKind::TimedOut, _) => {
+                            "no beacon: check CLK_EN, the clock divider and the UART TTY"
+                                .to_string()
+                        }
+                        _ => format!("no beacon ({err}): check the UART TTY"),
+                    });
+                }
+            }
+            Step::Nock => {
+                self.uart.nock(timeout).map_err(|err| match err.kind() {
+                    io::ErrorKind::TimedOut => {
+                        "no nock acknowledgement: check that the bootloader is running".to_string()
+                    }
+                    _ => format!("nock rejected ({err}): power-cycle the board"),
+                })?;
+            }
+            Step::CheckScratchpad { words } => {
+                let bebe = |err: io::Error| format!("no response from bebe ({err})");
+                let expected =
+                    |i: u64| 0xA5A5_5A5A_0000_0000 ^ i.wrapping_mul(0x0101_0101_0101_0101);
+                for i in 0..words {
+                    self.uart
+                        .write_u64(SCRATCHPAD_BASE_ADDR + i * 8, expected(i))
+                        .map_err(bebe)?;
+                }
+                for i in 0..words {
+                    let addr = SCRATCHPAD_BASE_ADDR + i * 8;
+                    let read = self.uart.read_u64(addr).map_err(bebe)?;
+                    if read != expected(i) {
+                        return Err(format!(
+                            "scratchpad readback mismatch at {addr:#x}: wrote {:#x}, read {read:#x}",
+                            expected(i)
+                        ));
+                    }
+                }
+            }
+            Step::CheckSramBist => {
+                let bebe = |err: io::Error| format!("no response from bebe ({err})");
+                let reg = sram_bist::ADDR;
+                let mask = u64::MAX >> (64 - reg.width());
+                for value in [0x5555_5555_5555_5555 & mask, 0xAAAA_AAAA_AAAA_AAAA & mask] {
+                    self.uart.write_u64(reg.addr(), value).map_err(bebe)?;
+                    let read = self.uart.read_u64(reg.addr()).map_err(bebe)?;
+                    if read & mask != value {
+                        return Err(format!(
+                            "SramBist {} readback mismatch: wrote {value:#x}, read {read:#x}",
+                            reg.name()
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,332 @@
+use crate::executor::{apply, Executor};
+use crate::fault::{Cell, DecoderFault, Fault, FaultClass, FaultyExecutor};
+use crate::pattern::{FixedPattern, FixedSramOp, SramAddr, SramSize};
+use rand_chacha::rand_core::{RngCore, SeedableRng};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A set of single faults against which a pattern is evaluated.
+#[derive(Clone, Debug)]
+pub struct FaultUniverse {
+    size: SramSize,
+    faults: Vec<Fault>,
+}
+
+impl FaultUniverse {
+    /// Every single fault of every class for the given SRAM size.
+    ///
+    /// The number of coupling and address decoder faults grows quadratically
+    /// with the number of cells, so this is only practical for small SRAMs.
+    pub fn exhaustive(size: SramSize) -> Self {
+        Self::exhaustive_of(size, &FaultClass::ALL)
+    }
+
+    /// Every single fault of the given classes for the given SRAM size.
+    pub fn exhaustive_of(size: SramSize, classes: &[FaultClass]) -> Self {
+        let cells = cells(size);
+        let mut faults = Vec::new();
+        for class in classes {
+            match class {
+                FaultClass::StuckAt => {
+                    for &cell in cells.iter() {
+                        for value in [false, true] {
+                            faults.push(Fault::StuckAt { cell, value });
+                        }
+                    }
+                }
+                FaultClass::Transition => {
+                    for &cell in cells.iter() {
+                        for rising in [false, true] {
+                            faults.push(Fault::Transition { cell, rising });
+                        }
+                    }
+                }
+                FaultClass::ReadDestructive => {
+                    for &cell in cells.iter() {
+                        for value in [false, true] {
+                            faults.push(Fault::ReadDestructive { cell, value });
+                        }
+                    }
+                }
+                FaultClass::DeceptiveReadDestructive => {
+                    for &cell in cells.iter() {
+                        for value in [false, true] {
+                            faults.push(Fault::DeceptiveReadDestructive { cell, value });
+                        }
+                    }
+                }
+                FaultClass::WriteDisturb => {
+                    for &cell in cells.iter() {
+                        for value in [false, true] {
+                            faults.push(Fault::WriteDisturb { cell, value });
+                        }
+                    }
+                }
+                FaultClass::DataRetention => {
+                    for &cell in cells.iter() {
+                        for value in [false, true] {
+                            faults.push(Fault::DataRetention { cell, value });
+                        }
+                    }
+                }
+                FaultClass::AddressDecoder => {
+                    for addr in 0..size.depth() {
+                        faults.push(Fault::AddressDecoder(DecoderFault::NoAccess { addr }));
+                        for other in (0..size.depth()).filter(|&other| other != addr) {
+                            faults.push(Fault::AddressDecoder(DecoderFault::Alias { addr, other }));
+                            faults.push(Fault::AddressDecoder(DecoderFault::Multiple {
+                                addr,
+                                other,
+                            }));
+                        }
+                    }
+                }
+                FaultClass::StateCoupling
+                | FaultClass::IdempotentCoupling
+                | FaultClass::InversionCoupling => {
+                    for &aggressor in cells.iter() {
+                        for &victim in cells.iter().filter(|&&victim| victim != aggressor) {
+                            faults.extend(coupling_faults(*class, aggressor, victim));
+                        }
+                    }
+                }
+            }
+        }
+        Self { size, faults }
+    }
+
+    /// A random sample of `per_class` faults from each fault class.
+    ///
+    /// Suitable for SRAMs too large for [`FaultUniverse::exhaustive`].
+    /// Samples are drawn with replacement.
+    pub fn sampled(size: SramSize, per_class: usize, seed: u64) -> Self {
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(seed);
+        let mut faults = Vec::new();
+        for class in FaultClass::ALL {
+            for _ in 0..per_class {
+                faults.push(random_fault(class, size, &mut rng));
+            }
+        }
+        Self { size, faults }
+    }
+
+    pub fn size(&self) -> SramSize {
+        self.size
+    }
+
+    pub fn faults(&self) -> &[Fault] {
+        &self.faults
+    }
+}
+
+fn cells(size: SramSize) -> Vec<Cell> {
+    (0..size.depth())
+        .flat_map(|addr| (0..size.width() as u32).map(move |bit| Cell { addr, bit }))
+        .collect()
+}
+
+fn coupling_faults(class: FaultClass, aggressor: Cell, victim: Cell) -> Vec<Fault> {
+    let mut faults = Vec::new();
+    for a in [false, true] {
+        match class {
+            FaultClass::StateCoupling => {
+                for v in [false, true] {
+                    faults.push(Fault::StateCoupling {
+                        aggressor,
+                        victim,
+                        aggressor_value: a,
+                        victim_value: v,
+                    });
+                }
+            }
+            FaultClass::IdempotentCoupling => {
+                for v in [false, true] {
+                    faults.push(Fault::IdempotentCoupling {
+                        aggressor,
+                        victim,
+                        rising: a,
+                        victim_value: v,
+                    });
+                }
+            }
+            FaultClass::InversionCoupling => faults.push(Fault::InversionCoupling {
+                aggressor,
+                victim,
+                rising: a,
+            }),
+            _ => unreachable!("not a coupling fault class"),
+        }
+    }
+    faults
+}
+
+fn random_fault(class: FaultClass, size: SramSize, rng: &mut impl RngCore) -> Fault {
+    let cell = |rng: &mut dyn RngCore| Cell {
+        addr: (rng.next_u64() % size.depth() as u64) as SramAddr,
+        bit: (rng.next_u64() % size.width()) as u32,
+    };
+    let value = rng.next_u32() & 1 > 0;
+    match class {
+        FaultClass::StuckAt => Fault::StuckAt {
+            cell: cell(rng),
+            value,
+        },
+        FaultClass::Transition => Fault::Transition {
+            cell: cell(rng),
+            rising: value,
+        },
+        FaultClass::ReadDestructive => Fault::ReadDestructive {
+            cell: cell(rng),
+            value,
+        },
+        FaultClass::DeceptiveReadDestructive => Fault::DeceptiveReadDestructive {
+            cell: cell(rng),
+            value,
+        },
+        FaultClass::WriteDisturb => Fault::WriteDisturb {
+            cell: cell(rng),
+            value,
+        },
+        FaultClass::DataRetention => Fault::DataRetention {
+            cell: cell(rng),
+            value,
+        },
+        FaultClass::AddressDecoder => {
+            assert!(
+                size.depth() > 1,
+                "address decoder faults require at least 2 words"
+            );
+            let addr = (rng.next_u64() % size.depth() as u64) as SramAddr;
+            let other = (addr + 1 + (rng.next_u64() % (size.depth() as u64 - 1)) as SramAddr)
+                % size.depth();
+            match rng.next_u32() % 3 {
+                0 => Fault::AddressDecoder(DecoderFault::NoAccess { addr }),
+                1 => Fault::AddressDecoder(DecoderFault::Alias { addr, other }),
+                _ => Fault::AddressDecoder(DecoderFault::Multiple { addr, other }),
+            }
+        }
+        FaultClass::StateCoupling
+        | FaultClass::IdempotentCoupling
+        | FaultClass::InversionCoupling => {
+            assert!(
+                size.depth() as u64 * size.width() > 1,
+                "coupling faults require at least 2 cells"
+            );
+            let aggressor = cell(rng);
+            let victim = loop {
+                let victim = cell(rng);
+                if victim != aggressor {
+                    break victim;
+                }
+            };
+            let faults = coupling_faults(class, aggressor, victim);
+            faults[(rng.next_u64() % faults.len() as u64) as usize]
+        }
+    }
+}
+
+/// Detection statistics for a single fault class.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ClassCoverage {
+    pub total: usize,
+    pub detected: usize,
+}
+
+impl ClassCoverage {
+    /// The fraction of faults detected, or `None` if the class is empty.
+    pub fn ratio(&self) -> Option<f64> {
+        if self.total == 0 {
+            None
+        } else {
+            Some(self.detected as f64 / self.total as f64)
+        }
+    }
+}
+
+/// The result of evaluating a pattern against a [`FaultUniverse`].
+#[derive(Clone, Debug)]
+pub struct CoverageReport {
+    classes: BTreeMap<FaultClass, ClassCoverage>,
+    undetected: Vec<Fault>,
+}
+
+impl CoverageReport {
+    pub fn class(&self, class: FaultClass) -> ClassCoverage {
+        self.classes.get(&class).copied().unwrap_or_default()
+    }
+
+    pub fn classes(&self) -> impl Iterator<Item = (FaultClass, ClassCoverage)> + '_ {
+        self.classes.iter().map(|(class, cov)| (*class, *cov))
+    }
+
+    /// The faults that the pattern failed to detect.
+    pub fn undetected(&self) -> &[Fault] {
+        &self.undetected
+    }
+
+    pub fn total(&self) -> ClassCoverage {
+        self.classes
+            .values()
+            .fold(ClassCoverage::default(), |acc, cov| ClassCoverage {
+                total: acc.total + cov.total,
+                detected: acc.detected + cov.detected,
+            })
+    }
+}
+
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total();
+        for (name, cov) in self
+            .classes()
+            .map(|(class, cov)| (class.name(), cov))
+            .chain(std::iter::once(("total", total)))
+        {
+            match cov.ratio() {
+                Some(ratio) => writeln!(
+                    f,
+                    "{name:<6} {:>8}/{:<8} {:>6.2}%",
+                    cov.detected,
+                    cov.total,
+                    ratio * 100.0
+                )?,
+                None => writeln!(f, "{name:<6} {:>8}/{:<8}      -", cov.detected, cov.total)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns `true` if running `ops` on `ex` produces at least one read mismatch.
+pub fn detects<E: Executor>(ops: &[FixedSramOp], ex: &mut E) -> bool {
+    ex.init();
+    let detected = ops.iter().any(|&op| apply(ex, op).is_some());
+    ex.finish();
+    detected
+}
+
+/// Runs `pattern` against every fault in `universe`, one fault at a time.
+pub fn analyze(pattern: &FixedPattern, universe: &FaultUniverse) -> CoverageReport {
+    assert_eq!(
+        pattern.size(),
+        universe.size(),
+        "pattern and fault universe must target the same SRAM size"
+    );
+    let ops: Vec<FixedSramOp> = pattern.ops().collect();
+    let mut classes = BTreeMap::new();
+    let mut undetected = Vec::new();
+    for &fault in universe.faults() {
+        let mut ex = FaultyExecutor::new(universe.size(), vec![fault]);
+        let cov: &mut ClassCoverage = classes.entry(fault.class()).or_default();
+        cov.total += 1;
+        if detects(&ops, &mut ex) {
+            cov.detected += 1;
+        } else {
+            undetected.push(fault);
+        }
+    }
+    CoverageReport {
+        classes,
+        undetected,
+    }
+}
@@ -0,0 +1,174 @@
+//! Comparison of host-driven and on-chip BIST runs of the same pattern.
+//!
+//! Running a pattern over the per-op UART path and on the BIST engine
+//! separates array defects, which both runs see, from artefacts of the host
+//! path, which only the slow run sees.
+//!
+//! Patterns that use the BIST random generator cannot be cross-checked,
+//! since the host could only replay the model's choices, which are not known
+//! to match the hardware's.
+//!
+//! Both the replayed ops and the predicted signature come from the
+//! unverified model in [`crate::golden`], so a disagreement cannot yet
+//! separate an array fault from a model error. Like the model, this is only
+//! built with the `unverified-bist` feature until the encoding and model are
+//! checked against the RTL.
+
+use crate::bist::BistOutcome;
+use crate::executor::{BistError, TestPatternErrors};
+use crate::golden::BistTrace;
+use crate::topology::SramTopology;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Where a host-driven run and a BIST engine run of the same ops disagree.
+///
+/// Op indices refer to the ops of the BIST trace, which both runs execute.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct CrossCheck {
+    /// Failures seen by both runs, which point to array defects.
+    pub array: Vec<BistError>,
+    /// Failures seen only by the host, in ops the BIST engine passed. These
+    /// point to the host path.
+    pub host_only: Vec<BistError>,
+    /// The first BIST failure, if the host passed the same op. This points
+    /// to defects that only show at speed.
+    pub bist_only: Option<BistError>,
+    /// Host failures after the first BIST failure, which the engine does not
+    /// report and so cannot be attributed. If the BIST failure could not be
+    /// located, this holds every host failure.
+    pub unchecked: Vec<BistError>,
+    /// A BIST failure that could not be located in the trace, with the raw
+    /// results of the run.
+    pub unlocated: Option<BistOutcome>,
+    /// The signature reported by the BIST engine.
+    pub signature: u32,
+    /// The signature the unverified model predicts for an ideal memory.
+    pub predicted_signature: u32,
+    fail: bool,
+}
+
+impl CrossCheck {
+    /// Compares the results of replaying `trace` over the host path with the
+    /// outcome of running the same program on the BIST engine.
+    pub fn new(
+        topology: &SramTopology,
+        trace: &BistTrace,
+        host: Result<(), TestPatternErrors>,
+        bist: &BistOutcome,
+        predicted_signature: u32,
+    ) -> Self {
+        let host = host.err().map_or_else(Vec::new, |errs| errs.errors);
+        let failure = trace.errors(topology, bist).err();
+        let bist_err = failure.and_then(|failure| failure.located);
+        let first = bist_err.map_or(usize::MAX, |err| err.op());
+
+        let mut check = Self {
+            array: Vec::new(),
+            host_only: Vec::new(),
+            bist_only: None,
+            unchecked: Vec::new(),
+            unlocated: failure
+                .filter(|failure| failure.located.is_none())
+                .map(|failure| failure.outcome),
+            signature: bist.signature,
+            predicted_signature,
+            fail: bist.fail,
+        };
+        for err in host {
+            if check.unlocated.is_some() {
+                check.unchecked.push(err);
+                continue;
+            }
+            match err.op().cmp(&first) {
+                Ordering::Less => check.host_only.push(err),
+                Ordering::Equal => check.array.push(err),
+                Ordering::Greater => check.unchecked.push(err),
+            }
+        }
+        if check.array.is_empty() {
+            check.bist_only = bist_err;
+        }
+        check
+    }
+
+    /// Whether the BIST signature matches the model's prediction. Only known
+    /// if the engine passed, since a failing run compacts unpredictable data.
+    ///
+    /// The model is unverified, so a mismatch does not point to a defect.
+    pub fn signature_matches_model(&self) -> Option<bool> {
+        (!self.fail).then_some(self.signature == self.predicted_signature)
+    }
+
+    /// Whether both runs agree, including on any array defects.
+    ///
+    /// The signature is not considered, since its prediction is unverified.
+    pub fn agrees(&self) -> bool {
+        self.host_only.is_empty() && self.bist_only.is_none() && self.unlocated.is_none()
+    }
+}
+
+/// A pattern that cannot be cross-checked, because the given element of its
+/// BIST program uses the random generator.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct RandomElementError {
+    pub element: usize,
+}
+
+impl fmt::Display for RandomElementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "element {} uses the BIST random generator, whose model is unverified",
+            self.element
+        )
+    }
+}
+
+impl std::error::Error for RandomElementError {}
+
+impl fmt::Display for CrossCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe = |err: &BistError| {
+            format!(
+                "op {} at {:#x}: got {:#x}, expected {:#x}",
+                err.op(),
+                err.addr(),
+                err.received(),
+                err.expected()
+            )
+        };
+        for err in &self.array {
+            writeln!(f, "array defect: {}", describe(err))?;
+        }
+        for err in &self.host_only {
+            writeln!(f, "host path only: {}", describe(err))?;
+        }
+        if let Some(err) = &self.bist_only {
+            writeln!(f, "BIST engine only: {}", describe(err))?;
+        }
+        if let Some(outcome) = &self.unlocated {
+            writeln!(
+                f,
+                "BIST failure in cycle {} not located: got {:#x}, expected {:#x}",
+                outcome.fail_cycle, outcome.received, outcome.expected
+            )?;
+        }
+        if !self.unchecked.is_empty() {
+            writeln!(
+                f,
+                "{} host failures after the first BIST failure",
+                self.unchecked.len()
+            )?;
+        }
+        match self.signature_matches_model() {
+            Some(true) => writeln!(f, "signature {:#x} matches model", self.signature),
+            Some(false) => writeln!(
+                f,
+                "signature {:#x}, model predicts {:#x} (unverified)",
+                self.signature, self.predicted_signature
+            ),
+            None => writeln!(f, "signature {:#x} not compared", self.signature),
+        }
+    }
+}
@@ -1,11 +1,35 @@
-use crate::pattern::{FixedPattern, FixedSramOp, SramAddr, SramSize, SramWord};
+use crate::pattern::{FixedPattern, FixedSramOp, SramAddr, SramInput, SramSize, SramWord};
 use crate::state::SramState;
+use std::time::Duration;
 
 pub trait Executor {
     fn init(&mut self);
     fn read(&mut self, addr: SramAddr) -> SramWord;
     fn write(&mut self, addr: SramAddr, data: SramWord, mask: SramWord);
 
+    /// Holds the array idle for the given duration.
+    fn pause(&mut self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+
+    /// Reads `addr` `count` times in a row, returning every value read.
+    ///
+    /// Executors that can issue back-to-back operations more cheaply
+    /// than individual reads should override this.
+    fn read_repeat(&mut self, addr: SramAddr, count: u32) -> Vec<SramWord> {
+        (0..count).map(|_| self.read(addr)).collect()
+    }
+
+    /// Writes `addr` `count` times in a row.
+    ///
+    /// Executors that can issue back-to-back operations more cheaply
+    /// than individual writes should override this.
+    fn write_repeat(&mut self, addr: SramAddr, data: SramWord, mask: SramWord, count: u32) {
+        for _ in 0..count {
+            self.write(addr, data, mask);
+        }
+    }
+
     fn finish(&mut self);
 }
 
@@ -23,19 +47,65 @@ impl IdealExecutor {
 impl Executor for IdealExecutor {
     fn init(&mut self) {}
 
+    /// Unwritten bits read as 0, so that capture reads of uninitialized
+    /// addresses, like those of [`Pattern::power_up`](crate::pattern::Pattern::power_up),
+    /// can run.
     fn read(&mut self, addr: SramAddr) -> SramWord {
-        self.state
-            .read(addr)
-            .expect("tried to read from an uninitialized address")
+        self.state.read_partial(addr).map_or(0, |(data, _)| data)
     }
 
     fn write(&mut self, addr: SramAddr, data: SramWord, mask: SramWord) {
         self.state.write(addr, data, mask);
     }
 
+    /// An ideal SRAM retains its contents indefinitely, so there is no need to wait.
+    fn pause(&mut self, _duration: Duration) {}
+
     fn finish(&mut self) {}
 }
 
+/// Applies a single operation without logging, returning the first
+/// mismatching value read, if any.
+pub fn apply<E: Executor>(ex: &mut E, op: FixedSramOp) -> Option<SramWord> {
+    match op {
+        FixedSramOp::Read {
+            addr,
+            data,
+            compare,
+        } => Some(ex.read(addr)).filter(|&dout| (dout ^ data) & compare != 0),
+        FixedSramOp::ReadRepeat {
+            addr,
+            data,
+            compare,
+            count,
+        } => ex
+            .read_repeat(addr, count)
+            .into_iter()
+            .find(|&dout| (dout ^ data) & compare != 0),
+        FixedSramOp::Write { addr, data, mask } => {
+            ex.write(addr, data, mask);
+            None
+        }
+        FixedSramOp::WriteRepeat {
+            addr,
+            data,
+            mask,
+            count,
+        } => {
+            ex.write_repeat(addr, data, mask, count);
+            None
+        }
+        FixedSramOp::Capture { addr } => {
+            ex.read(addr);
+            None
+        }
+        FixedSramOp::Pause { duration } => {
+            ex.pause(duration);
+            None
+        }
+    }
+}
+
 /// A collection of all errors produced by executing a test.
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct TestPatternErrors {
@@ -45,12 +115,94 @@ pub struct TestPatternErrors {
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct BistError {
     op: usize,
+    addr: SramAddr,
     expected: SramWord,
     received: SramWord,
+    compare: SramWord,
+}
+
+impl BistError {
+    #[cfg(feature = "unverified-bist")]
+    pub(crate) fn new(
+        op: usize,
+        addr: SramAddr,
+        expected: SramWord,
+        received: SramWord,
+        compare: SramWord,
+    ) -> Self {
+        Self {
+            op,
+            addr,
+            expected,
+            received,
+            compare,
+        }
+    }
+
+    pub fn op(&self) -> usize {
+        self.op
+    }
+    pub fn addr(&self) -> SramAddr {
+        self.addr
+    }
+    pub fn expected(&self) -> SramWord {
+        self.expected
+    }
+    pub fn received(&self) -> SramWord {
+        self.received
+    }
+    pub fn compare(&self) -> SramWord {
+        self.compare
+    }
+
+    /// The significant bits that differed between the expected and received values.
+    pub fn failing_bits(&self) -> SramWord {
+        (self.expected ^ self.received) & self.compare
+    }
+
+    /// Decodes which address the received value belongs to, trying each of the
+    /// address-dependent `inputs` the pattern wrote in turn.
+    pub fn owner(&self, inputs: &[SramInput], size: SramSize) -> Option<SramAddr> {
+        inputs
+            .iter()
+            .find_map(|input| input.owner(self.received, size))
+    }
+
+    /// Describes the mismatch as logged by [`execute`], naming the address
+    /// the received value belongs to if [`BistError::owner`] can decode it.
+    pub fn describe(&self, inputs: &[SramInput], size: SramSize) -> String {
+        let mut desc = format!(
+            "got {:#x}, expected {:#x} (compare mask {:#x}, bits {:#x} differ)",
+            self.received,
+            self.expected,
+            self.compare,
+            self.failing_bits()
+        );
+        if let Some(owner) = self.owner(inputs, size) {
+            desc += &format!(", data belongs to {owner:#x}");
+        }
+        desc
+    }
+}
+
+/// A value recorded by a capture read.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Capture {
+    pub op: usize,
+    pub addr: SramAddr,
+    pub data: SramWord,
 }
 
 /// Executes a test sequence.
-pub fn execute<E: Executor>(pattern: FixedPattern, mut ex: E) -> Result<(), TestPatternErrors> {
+pub fn execute<E: Executor>(pattern: FixedPattern, ex: E) -> Result<(), TestPatternErrors> {
+    execute_capturing(pattern, ex).map(|_| ())
+}
+
+/// Executes a test sequence, returning the values recorded by capture reads.
+pub fn execute_capturing<E: Executor>(
+    pattern: FixedPattern,
+    mut ex: E,
+) -> Result<Vec<Capture>, TestPatternErrors> {
     println!("Beginning SRAM BIST test");
 
     ex.init();
@@ -72,29 +224,39 @@ pub fn execute_starting_at<E: Executor>(
     let res = execute_inner(pattern, &mut ex, offset);
     ex.finish();
 
-    res
+    res.map(|_| ())
 }
 
 fn execute_inner<E: Executor>(
     pattern: FixedPattern,
     ex: &mut E,
     ofs: usize,
-) -> Result<(), TestPatternErrors> {
+) -> Result<Vec<Capture>, TestPatternErrors> {
+    let inputs = pattern.addr_inputs();
+    let size = pattern.size();
     let mut errors = Vec::new();
+    let mut captures = Vec::new();
     for (i, op) in pattern.ops().enumerate().skip(ofs) {
         match op {
-            FixedSramOp::Read { data, addr } => {
+            FixedSramOp::Read {
+                data,
+                addr,
+                compare,
+            } => {
                 print!("Reading {addr:#x}...\t");
                 let dout = ex.read(addr);
-                if dout == data {
+                if (dout ^ data) & compare == 0 {
                     println!("OK (received {dout:#x})");
                 } else {
-                    println!("ERROR: got {dout:#x}, expected {data:#x}");
-                    errors.push(BistError {
+                    let err = BistError {
                         op: i,
+                        addr,
                         expected: data,
                         received: dout,
-                    });
+                        compare,
+                    };
+                    println!("ERROR: {}", err.describe(&inputs, size));
+                    errors.push(err);
                 }
             }
             FixedSramOp::Write { data, addr, mask } => {
@@ -102,11 +264,64 @@ fn execute_inner<E: Executor>(
                 ex.write(addr, data, mask);
                 println!("DONE");
             }
+            FixedSramOp::Capture { addr } => {
+                print!("Capturing {addr:#x}...\t");
+                let dout = ex.read(addr);
+                println!("DONE (received {dout:#x})");
+                captures.push(Capture {
+                    op: i,
+                    addr,
+                    data: dout,
+                });
+            }
+            FixedSramOp::Pause { duration } => {
+                print!("Pausing for {duration:?}...\t");
+                ex.pause(duration);
+                println!("DONE");
+            }
+            FixedSramOp::ReadRepeat {
+                addr,
+                data,
+                compare,
+                count,
+            } => {
+                print!("Reading {addr:#x} {count} times...\t");
+                let douts = ex.read_repeat(addr, count);
+                match douts.iter().position(|&dout| (dout ^ data) & compare != 0) {
+                    None => match douts.last() {
+                        Some(dout) => println!("OK (received {dout:#x})"),
+                        None => println!("OK (no reads)"),
+                    },
+                    Some(n) => {
+                        let err = BistError {
+                            op: i,
+                            addr,
+                            expected: data,
+                            received: douts[n],
+                            compare,
+                        };
+                        println!("ERROR on read {n}: {}", err.describe(&inputs, size));
+                        errors.push(err);
+                    }
+                }
+            }
+            FixedSramOp::WriteRepeat {
+                addr,
+                data,
+                mask,
+                count,
+            } => {
+                print!(
+                    "Writing {addr:#x} {count} times with data = {data:#x}, mask = {mask:#x}...\t"
+                );
+                ex.write_repeat(addr, data, mask, count);
+                println!("DONE");
+            }
         }
     }
 
     if errors.is_empty() {
-        Ok(())
+        Ok(captures)
     } else {
         Err(TestPatternErrors { errors })
     }
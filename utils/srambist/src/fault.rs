@@ -0,0 +1,391 @@
+use crate::executor::Executor;
+use crate::pattern::{SramAddr, SramSize, SramWord};
+use crate::state::SramState;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// A single bit cell in the SRAM array.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+pub struct Cell {
+    pub addr: SramAddr,
+    pub bit: u32,
+}
+
+/// A functional fault primitive.
+///
+/// Transitions are described by `rising`: `true` for a 0 to 1 transition,
+/// `false` for a 1 to 0 transition.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum Fault {
+    /// The cell always holds `value`.
+    StuckAt { cell: Cell, value: bool },
+    /// The cell cannot make the given transition.
+    Transition { cell: Cell, rising: bool },
+    /// A fault in the address decoder.
+    AddressDecoder(DecoderFault),
+    /// The victim is forced to `victim_value` whenever the aggressor holds `aggressor_value`.
+    StateCoupling {
+        aggressor: Cell,
+        victim: Cell,
+        aggressor_value: bool,
+        victim_value: bool,
+    },
+    /// A transition of the aggressor forces the victim to `victim_value`.
+    IdempotentCoupling {
+        aggressor: Cell,
+        victim: Cell,
+        rising: bool,
+        victim_value: bool,
+    },
+    /// A transition of the aggressor inverts the victim.
+    InversionCoupling {
+        aggressor: Cell,
+        victim: Cell,
+        rising: bool,
+    },
+    /// Reading the cell while it holds `value` flips it and returns the flipped value.
+    ReadDestructive { cell: Cell, value: bool },
+    /// Reading the cell while it holds `value` returns the correct value but flips the cell.
+    DeceptiveReadDestructive { cell: Cell, value: bool },
+    /// A non-transition write of `value` to the cell flips it.
+    WriteDisturb { cell: Cell, value: bool },
+    /// The cell loses `value`, flipping to the opposite value, while the array is idle.
+    DataRetention { cell: Cell, value: bool },
+}
+
+/// A fault in the address decoder.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum DecoderFault {
+    /// `addr` does not access any word. Writes are lost and reads return 0.
+    NoAccess { addr: SramAddr },
+    /// `addr` accesses the word at `other` instead of its own.
+    Alias { addr: SramAddr, other: SramAddr },
+    /// `addr` accesses both its own word and the word at `other`.
+    ///
+    /// Reads return the wired-AND of both words.
+    Multiple { addr: SramAddr, other: SramAddr },
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+pub enum FaultClass {
+    StuckAt,
+    Transition,
+    AddressDecoder,
+    StateCoupling,
+    IdempotentCoupling,
+    InversionCoupling,
+    ReadDestructive,
+    DeceptiveReadDestructive,
+    WriteDisturb,
+    DataRetention,
+}
+
+impl FaultClass {
+    pub const ALL: [FaultClass; 10] = [
+        FaultClass::StuckAt,
+        FaultClass::Transition,
+        FaultClass::AddressDecoder,
+        FaultClass::StateCoupling,
+        FaultClass::IdempotentCoupling,
+        FaultClass::InversionCoupling,
+        FaultClass::ReadDestructive,
+        FaultClass::DeceptiveReadDestructive,
+        FaultClass::WriteDisturb,
+        FaultClass::DataRetention,
+    ];
+
+    /// The conventional abbreviation for the fault class.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FaultClass::StuckAt => "SAF",
+            FaultClass::Transition => "TF",
+            FaultClass::AddressDecoder => "AF",
+            FaultClass::StateCoupling => "CFst",
+            FaultClass::IdempotentCoupling => "CFid",
+            FaultClass::InversionCoupling => "CFin",
+            FaultClass::ReadDestructive => "RDF",
+            FaultClass::DeceptiveReadDestructive => "DRDF",
+            FaultClass::WriteDisturb => "WDF",
+            FaultClass::DataRetention => "DRF",
+        }
+    }
+}
+
+impl Fault {
+    pub fn class(&self) -> FaultClass {
+        match self {
+            Fault::StuckAt { .. } => FaultClass::StuckAt,
+            Fault::Transition { .. } => FaultClass::Transition,
+            Fault::AddressDecoder(_) => FaultClass::AddressDecoder,
+            Fault::StateCoupling { .. } => FaultClass::StateCoupling,
+            Fault::IdempotentCoupling { .. } => FaultClass::IdempotentCoupling,
+            Fault::InversionCoupling { .. } => FaultClass::InversionCoupling,
+            Fault::ReadDestructive { .. } => FaultClass::ReadDestructive,
+            Fault::DeceptiveReadDestructive { .. } => FaultClass::DeceptiveReadDestructive,
+            Fault::WriteDisturb { .. } => FaultClass::WriteDisturb,
+            Fault::DataRetention { .. } => FaultClass::DataRetention,
+        }
+    }
+}
+
+/// An executor that simulates an SRAM with injected functional faults.
+///
+/// Uninitialized words are assumed to power up as all zeros.
+#[derive(Clone)]
+pub struct FaultyExecutor {
+    size: SramSize,
+    state: SramState,
+    faults: Vec<Fault>,
+}
+
+impl FaultyExecutor {
+    pub fn new(size: SramSize, faults: Vec<Fault>) -> Self {
+        for fault in faults.iter() {
+            check_fault(size, fault);
+        }
+        let mut ex = Self {
+            size,
+            state: SramState::new(size),
+            faults,
+        };
+        for addr in 0..size.depth() {
+            ex.store(addr, 0);
+        }
+        ex.apply_static_faults();
+        ex
+    }
+
+    pub fn faults(&self) -> &[Fault] {
+        &self.faults
+    }
+
+    fn full_mask(&self) -> SramWord {
+        u64::MAX >> (64 - self.size.mask_width())
+    }
+
+    fn load(&self, addr: SramAddr) -> SramWord {
+        self.state.read_partial(addr).map_or(0, |(data, _)| data)
+    }
+
+    fn store(&mut self, addr: SramAddr, data: SramWord) {
+        let mask = self.full_mask();
+        self.state.write(addr, data, mask);
+    }
+
+    fn get(&self, cell: Cell) -> bool {
+        self.load(cell.addr) & (1 << cell.bit) != 0
+    }
+
+    fn set(&mut self, cell: Cell, value: bool) {
+        let word = self.load(cell.addr);
+        let word = if value {
+            word | (1 << cell.bit)
+        } else {
+            word & !(1 << cell.bit)
+        };
+        self.store(cell.addr, word);
+    }
+
+    /// The words actually accessed by the decoder for `addr`.
+    fn decode(&self, addr: SramAddr) -> Vec<SramAddr> {
+        let mut words = vec![addr];
+        for fault in self.faults.iter() {
+            if let Fault::AddressDecoder(fault) = fault {
+                match *fault {
+                    DecoderFault::NoAccess { addr: a } if a == addr => words.clear(),
+                    DecoderFault::Alias { addr: a, other } if a == addr => words = vec![other],
+                    DecoderFault::Multiple { addr: a, other } if a == addr => words.push(other),
+                    _ => {}
+                }
+            }
+        }
+        words
+    }
+
+    /// Applies faults that constrain cell state regardless of the operation performed.
+    fn apply_static_faults(&mut self) {
+        for i in 0..self.faults.len() {
+            match self.faults[i] {
+                Fault::StuckAt { cell, value } => self.set(cell, value),
+                Fault::StateCoupling {
+                    aggressor,
+                    victim,
+                    aggressor_value,
+                    victim_value,
+                } if self.get(aggressor) == aggressor_value => self.set(victim, victim_value),
+                _ => {}
+            }
+        }
+    }
+
+    fn write_word(&mut self, addr: SramAddr, data: SramWord, bits: SramWord) {
+        let old = self.load(addr);
+        let mut new = (old & !bits) | (data & bits);
+
+        for fault in self.faults.iter() {
+            match *fault {
+                Fault::Transition { cell, rising } if cell.addr == addr => {
+                    let bit = 1 << cell.bit;
+                    let from = old & bit != 0;
+                    let to = new & bit != 0;
+                    if from != to && to == rising {
+                        new ^= bit;
+                    }
+                }
+                Fault::WriteDisturb { cell, value } if cell.addr == addr => {
+                    let bit = 1 << cell.bit;
+                    let from = old & bit != 0;
+                    let to = new & bit != 0;
+                    if bits & bit != 0 && from == value && to == value {
+                        new ^= bit;
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.store(addr, new);
+
+        for i in 0..self.faults.len() {
+            match self.faults[i] {
+                Fault::IdempotentCoupling {
+                    aggressor,
+                    victim,
+                    rising,
+                    victim_value,
+                } if aggressor.addr == addr && transitioned(old, new, aggressor.bit, rising) => {
+                    self.set(victim, victim_value);
+                }
+                Fault::InversionCoupling {
+                    aggressor,
+                    victim,
+                    rising,
+                } if aggressor.addr == addr && transitioned(old, new, aggressor.bit, rising) => {
+                    let value = self.get(victim);
+                    self.set(victim, !value);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn read_word(&mut self, addr: SramAddr) -> SramWord {
+        let stored = self.load(addr);
+        let mut out = stored;
+        let mut next = stored;
+        for fault in self.faults.iter() {
+            match *fault {
+                Fault::ReadDestructive { cell, value } if cell.addr == addr => {
+                    let bit = 1 << cell.bit;
+                    if (stored & bit != 0) == value {
+                        out ^= bit;
+                        next ^= bit;
+                    }
+                }
+                Fault::DeceptiveReadDestructive { cell, value } if cell.addr == addr => {
+                    let bit = 1 << cell.bit;
+                    if (stored & bit != 0) == value {
+                        next ^= bit;
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.store(addr, next);
+        out
+    }
+}
+
+fn transitioned(old: SramWord, new: SramWord, bit: u32, rising: bool) -> bool {
+    let from = old & (1 << bit) != 0;
+    let to = new & (1 << bit) != 0;
+    from != to && to == rising
+}
+
+fn check_cell(size: SramSize, cell: Cell) {
+    assert!(cell.addr < size.depth(), "fault cell address out of bounds");
+    assert!(
+        (cell.bit as SramWord) < size.width(),
+        "fault cell bit out of bounds"
+    );
+}
+
+fn check_fault(size: SramSize, fault: &Fault) {
+    match *fault {
+        Fault::StuckAt { cell, .. }
+        | Fault::Transition { cell, .. }
+        | Fault::ReadDestructive { cell, .. }
+        | Fault::DeceptiveReadDestructive { cell, .. }
+        | Fault::WriteDisturb { cell, .. }
+        | Fault::DataRetention { cell, .. } => check_cell(size, cell),
+        Fault::StateCoupling {
+            aggressor, victim, ..
+        }
+        | Fault::IdempotentCoupling {
+            aggressor, victim, ..
+        }
+        | Fault::InversionCoupling {
+            aggressor, victim, ..
+        } => {
+            check_cell(size, aggressor);
+            check_cell(size, victim);
+            assert_ne!(aggressor, victim, "aggressor and victim must be distinct");
+        }
+        Fault::AddressDecoder(fault) => match fault {
+            DecoderFault::NoAccess { addr } => {
+                assert!(addr < size.depth(), "fault address out of bounds")
+            }
+            DecoderFault::Alias { addr, other } | DecoderFault::Multiple { addr, other } => {
+                assert!(addr < size.depth(), "fault address out of bounds");
+                assert!(other < size.depth(), "fault address out of bounds");
+                assert_ne!(addr, other, "decoder fault addresses must be distinct");
+            }
+        },
+    }
+}
+
+impl Executor for FaultyExecutor {
+    fn init(&mut self) {}
+
+    fn read(&mut self, addr: SramAddr) -> SramWord {
+        assert!(addr < self.size.depth(), "addr out of bounds");
+        let words = self.decode(addr);
+        let data = if words.is_empty() {
+            0
+        } else {
+            words
+                .into_iter()
+                .map(|word| self.read_word(word))
+                .fold(u64::MAX, |acc, word| acc & word)
+        };
+        self.apply_static_faults();
+        data
+    }
+
+    fn write(&mut self, addr: SramAddr, data: SramWord, mask: SramWord) {
+        assert!(addr < self.size.depth(), "addr out of bounds");
+        let mask_gran = self.size.width() / self.size.mask_width();
+        let mut bits = 0;
+        for i in 0..self.size.mask_width() {
+            if mask & (1 << i) > 0 {
+                bits |= (u64::MAX >> (64 - mask_gran)) << (i * mask_gran);
+            }
+        }
+        for word in self.decode(addr) {
+            self.write_word(word, data, bits);
+        }
+        self.apply_static_faults();
+    }
+
+    fn pause(&mut self, _duration: Duration) {
+        for i in 0..self.faults.len() {
+            if let Fault::DataRetention { cell, value } = self.faults[i] {
+                if self.get(cell) == value {
+                    self.set(cell, !value);
+                }
+            }
+        }
+        self.apply_static_faults();
+    }
+
+    fn finish(&mut self) {}
+}
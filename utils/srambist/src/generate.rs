@@ -0,0 +1,251 @@
+use crate::coverage::{analyze, FaultUniverse};
+use crate::executor::apply;
+use crate::fault::{Fault, FaultClass, FaultyExecutor};
+use crate::pattern::{
+    AddrScope, AddrSeq, Element, FixedPattern, FixedSramOp, Pattern, SramInput, SramOp, SramSize,
+    SramWord,
+};
+
+/// Parameters for the march test search.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct GenerateConfig {
+    /// The maximum number of operations in a single march element.
+    pub max_element_ops: usize,
+    /// The maximum number of march elements, including the initializing write.
+    pub max_elements: usize,
+}
+
+impl Default for GenerateConfig {
+    fn default() -> Self {
+        Self {
+            max_element_ops: 3,
+            max_elements: 8,
+        }
+    }
+}
+
+/// A synthesized march test.
+#[derive(Clone, Debug)]
+pub struct GeneratedTest {
+    pub pattern: Pattern,
+    /// Faults in the target universe that the pattern does not detect.
+    pub undetected: Vec<Fault>,
+}
+
+impl GeneratedTest {
+    /// The number of operations applied to each address.
+    pub fn ops_per_addr(&self) -> usize {
+        self.pattern.elements.iter().map(|elt| elt.ops.len()).sum()
+    }
+}
+
+/// Synthesizes a short march test detecting every fault of the given classes.
+///
+/// Searches against the exhaustive fault universe, so `size` should be small.
+/// March tests are regular, so a test found for a small SRAM detects the same
+/// fault classes on larger SRAMs.
+pub fn generate(classes: &[FaultClass], size: SramSize) -> GeneratedTest {
+    generate_for(
+        &FaultUniverse::exhaustive_of(size, classes),
+        GenerateConfig::default(),
+    )
+}
+
+/// Synthesizes a short march test detecting the faults in `universe`.
+///
+/// Starting from an initializing write, march elements are appended greedily,
+/// picking the element that detects the most new faults per operation.
+/// Elements that do not contribute to coverage are then pruned.
+pub fn generate_for(universe: &FaultUniverse, config: GenerateConfig) -> GeneratedTest {
+    assert!(config.max_elements > 0, "must allow at least one element");
+    assert!(
+        config.max_element_ops > 0,
+        "must allow at least one operation per element"
+    );
+    let size = universe.size();
+    let init = Element {
+        addr_seq: AddrSeq::Up,
+        scope: AddrScope::Full,
+        ops: vec![write(false)],
+    };
+    let mut elements = vec![init.clone()];
+    let mut background = false;
+
+    // Simulator state after the current prefix, for each fault not yet detected.
+    let mut pending: Vec<FaultyExecutor> = universe
+        .faults()
+        .iter()
+        .filter_map(|&fault| {
+            let mut ex = FaultyExecutor::new(size, vec![fault]);
+            let (ops, _) = expand(&init, size, false);
+            (!run(&ops, &mut ex)).then_some(ex)
+        })
+        .collect();
+
+    let candidates = candidate_ops(config.max_element_ops);
+    while !pending.is_empty() && elements.len() < config.max_elements {
+        let mut best: Option<(Element, usize)> = None;
+        for ops in candidates.iter() {
+            for addr_seq in [AddrSeq::Up, AddrSeq::Down] {
+                let elt = Element {
+                    addr_seq,
+                    scope: AddrScope::Full,
+                    ops: ops.clone(),
+                };
+                let (fixed, _) = expand(&elt, size, background);
+                let detected = pending
+                    .iter()
+                    .filter(|ex| run(&fixed, &mut (*ex).clone()))
+                    .count();
+                let better = match &best {
+                    None => detected > 0,
+                    Some((best, best_detected)) => {
+                        detected * best.ops.len() > best_detected * elt.ops.len()
+                    }
+                };
+                if better {
+                    best = Some((elt, detected));
+                }
+            }
+        }
+
+        let Some((elt, _)) = best else {
+            break;
+        };
+        let (fixed, next) = expand(&elt, size, background);
+        pending.retain_mut(|ex| !run(&fixed, ex));
+        background = next;
+        elements.push(elt);
+    }
+
+    // Greedy selection can leave elements made redundant by later ones.
+    let target = detected_count(&elements, universe);
+    let mut i = 1;
+    while i < elements.len() {
+        let mut pruned = elements.clone();
+        pruned.remove(i);
+        if detected_count(&pruned, universe) >= target {
+            elements = pruned;
+        } else {
+            i += 1;
+        }
+    }
+
+    let pattern = Pattern { elements };
+    let undetected = analyze(&FixedPattern::new(pattern.clone(), size, 0), universe)
+        .undetected()
+        .to_vec();
+    GeneratedTest {
+        pattern,
+        undetected,
+    }
+}
+
+fn write(value: bool) -> SramOp {
+    SramOp::Write {
+        data: SramInput::Fixed(if value { u64::MAX } else { 0 }),
+        mask: SramInput::Fixed(u64::MAX),
+    }
+}
+
+/// All non-empty sequences of reads and solid writes up to `max_len` long.
+///
+/// Sequences containing back-to-back identical writes are skipped, since the
+/// second write cannot sensitize anything the first did not.
+fn candidate_ops(max_len: usize) -> Vec<Vec<SramOp>> {
+    let alphabet = [SramOp::Read, write(false), write(true)];
+    let mut all: Vec<Vec<SramOp>> = vec![vec![]];
+    let mut out = Vec::new();
+    for _ in 0..max_len {
+        let mut next = Vec::new();
+        for prefix in all.iter() {
+            for op in alphabet {
+                if matches!(op, SramOp::Write { .. }) && prefix.last() == Some(&op) {
+                    continue;
+                }
+                let mut ops = prefix.clone();
+                ops.push(op);
+                next.push(ops);
+            }
+        }
+        out.extend(next.iter().cloned());
+        all = next;
+    }
+    out
+}
+
+/// Expands a solid-background element, returning its operations and the
+/// resulting background.
+fn expand(elt: &Element, size: SramSize, background: bool) -> (Vec<FixedSramOp>, bool) {
+    let dmask = u64::MAX >> (64 - size.width());
+    let mask = u64::MAX >> (64 - size.mask_width());
+    let word = |value: bool| -> SramWord {
+        if value {
+            dmask
+        } else {
+            0
+        }
+    };
+    let addrs: Vec<u32> = match elt.addr_seq {
+        AddrSeq::Up => (0..size.depth()).collect(),
+        AddrSeq::Down => (0..size.depth()).rev().collect(),
+        _ => unreachable!("generated elements only sweep up or down"),
+    };
+    let mut ops = Vec::new();
+    for addr in addrs {
+        let mut value = background;
+        for op in elt.ops.iter() {
+            match op {
+                SramOp::Read => ops.push(FixedSramOp::Read {
+                    addr,
+                    data: word(value),
+                    compare: dmask,
+                }),
+                SramOp::Write {
+                    data: SramInput::Fixed(data),
+                    ..
+                } => {
+                    value = *data != 0;
+                    ops.push(FixedSramOp::Write {
+                        addr,
+                        data: word(value),
+                        mask,
+                    });
+                }
+                _ => unreachable!("generated elements only contain reads and solid writes"),
+            }
+        }
+    }
+    let next = elt
+        .ops
+        .iter()
+        .rev()
+        .find_map(|op| match op {
+            SramOp::Write {
+                data: SramInput::Fixed(data),
+                ..
+            } => Some(*data != 0),
+            _ => None,
+        })
+        .unwrap_or(background);
+    (ops, next)
+}
+
+/// Runs `ops`, returning `true` if a read mismatch was observed.
+fn run(ops: &[FixedSramOp], ex: &mut FaultyExecutor) -> bool {
+    // Every op must be applied, since the executor state is reused.
+    let mut detected = false;
+    for &op in ops {
+        detected |= apply(ex, op).is_some();
+    }
+    detected
+}
+
+fn detected_count(elements: &[Element], universe: &FaultUniverse) -> usize {
+    let pattern = Pattern {
+        elements: elements.to_vec(),
+    };
+    analyze(&FixedPattern::new(pattern, universe.size(), 0), universe)
+        .total()
+        .detected
+}
@@ -0,0 +1,426 @@
+//! A model of the SramBist BIST engine, including its random generator and
+//! signature compactor.
+//!
+//! Running a [`BistProgram`] against a memory records which op the model
+//! runs in every cycle, so that `BIST_FAIL_CYCLE` can be traced back to an
+//! element, address and op, and predicts a `BIST_SIGNATURE`.
+//!
+//! # Unverified model
+//!
+//! The SramBist RTL is not part of this repository, and nothing in it
+//! specifies the engine's timing, random generator or compactor. The
+//! generator taps, the compactor polynomial, the table of random fields
+//! below, and the assumptions that the engine runs one op per cycle and
+//! steps its generator once per op are all guesses. Until they are checked
+//! against RTL simulation, predicted signatures and failure locations are
+//! not expected hardware results, and a mismatch with hardware says nothing
+//! about the array.
+//!
+//! This is not yet the bit-accurate model the engine needs, so it is only
+//! built with the `unverified-bist` feature. The taps, polynomial, field
+//! layout and timing must be derived from the RTL, and tested against
+//! signatures from its simulation, before it is built by default.
+//!
+//! The model executes one op per cycle. Before every op, the random generator
+//! advances one step; the op then draws its random fields from the new state:
+//!
+//! | bits    | field                                    |
+//! |---------|------------------------------------------|
+//! | 0..32   | write data                               |
+//! | 32..64  | write mask                               |
+//! | 64      | random op: write if set, read otherwise  |
+//! | 65..75  | row of random address elements           |
+//! | 75..78  | column select of random address elements |
+//!
+//! Random address elements draw a new address in the first op cycle at each
+//! address, taking the row and column select modulo their maximum values.
+
+use crate::bist::{
+    BistOp, BistOpType, BistOutcome, BistProgram, BistRunConfig, BistSource, InnerDim,
+    RAND_SEED_BITS,
+};
+use crate::executor::{apply, BistError, Executor, TestPatternErrors};
+use crate::pattern::{FixedSramOp, SramAddr, SramWord};
+use crate::topology::SramTopology;
+use std::fmt;
+
+/// A model of the 78-bit random generator behind `BIST_RAND_SEED`: a
+/// Fibonacci LFSR with the assumed feedback polynomial
+/// `x^78 + x^77 + x^59 + x^58 + 1`.
+///
+/// An all-zero seed locks the generator at zero.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Lfsr {
+    state: u128,
+}
+
+/// The assumed taps of the random generator, as bit indices.
+const LFSR_TAPS: [u32; 4] = [77, 76, 58, 57];
+
+impl Lfsr {
+    pub fn new(seed: u128) -> Self {
+        Self {
+            state: seed & Self::mask(),
+        }
+    }
+
+    fn mask() -> u128 {
+        (1 << RAND_SEED_BITS) - 1
+    }
+
+    pub fn state(&self) -> u128 {
+        self.state
+    }
+
+    /// Shifts the generator by one bit.
+    pub fn step(&mut self) {
+        let feedback = LFSR_TAPS
+            .iter()
+            .fold(0, |fb, &tap| fb ^ ((self.state >> tap) & 1));
+        self.state = (self.state << 1 | feedback) & Self::mask();
+    }
+
+    /// The `width` bits starting at bit `offset` of the current state.
+    fn field(&self, offset: u32, width: u32) -> u64 {
+        ((self.state >> offset) & ((1 << width) - 1)) as u64
+    }
+}
+
+/// A model of the 32-bit signature register behind `BIST_SIGNATURE`: a
+/// multiple-input signature register with the assumed feedback polynomial
+/// `x^32 + x^22 + x^2 + x + 1`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Misr {
+    signature: u32,
+}
+
+const MISR_POLY: u32 = 0x0040_0007;
+
+impl Misr {
+    pub fn new(seed: u32) -> Self {
+        Self { signature: seed }
+    }
+
+    pub fn signature(&self) -> u32 {
+        self.signature
+    }
+
+    /// Shifts the register by one bit and folds in `data`.
+    pub fn fold(&mut self, data: u32) {
+        let feedback = if self.signature >> 31 != 0 {
+            MISR_POLY
+        } else {
+            0
+        };
+        self.signature = (self.signature << 1) ^ feedback ^ data;
+    }
+}
+
+/// An op executed by the model.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct TracedOp {
+    /// The cycle in which the op started.
+    pub cycle: u64,
+    pub element: usize,
+    pub op: FixedSramOp,
+    /// Whether the model's random generator chose any part of the op.
+    pub random: bool,
+}
+
+/// The ops executed by the model, in order.
+///
+/// The index of each op matches the index of the corresponding op of
+/// [`BistProgram::pattern`], although random ops are resolved to the read or
+/// write the engine chose.
+#[derive(Clone, Debug, Default)]
+pub struct BistTrace {
+    ops: Vec<TracedOp>,
+}
+
+impl BistTrace {
+    pub fn ops(&self) -> &[TracedOp] {
+        &self.ops
+    }
+
+    /// The index of the op executing in the given cycle, and the op itself.
+    pub fn at_cycle(&self, cycle: u64) -> Option<(usize, &TracedOp)> {
+        let idx = self.ops.partition_point(|op| op.cycle <= cycle);
+        let (idx, op) = (idx.checked_sub(1)?, &self.ops[idx - 1]);
+        (cycle == op.cycle).then_some((idx, op))
+    }
+
+    /// Runs the traced ops on `ex`, reporting every failing read.
+    ///
+    /// This issues the same accesses as the model of the BIST engine, but at
+    /// the speed of `ex`.
+    ///
+    /// # Panics
+    ///
+    /// If the model's random generator chose any op, since the hardware's
+    /// generator is not known to match it.
+    pub fn replay<E: Executor>(&self, mut ex: E) -> Result<(), TestPatternErrors> {
+        if let Some(traced) = self.ops.iter().find(|traced| traced.random) {
+            panic!(
+                "cannot replay element {}, which uses the unverified random generator model",
+                traced.element
+            );
+        }
+        let mut errors = Vec::new();
+        ex.init();
+        for (i, traced) in self.ops.iter().enumerate() {
+            if let (
+                Some(received),
+                FixedSramOp::Read {
+                    addr,
+                    data,
+                    compare,
+                },
+            ) = (apply(&mut ex, traced.op), traced.op)
+            {
+                errors.push(BistError::new(i, addr, data, received, compare));
+            }
+        }
+        ex.finish();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(TestPatternErrors { errors })
+        }
+    }
+
+    /// Locates the failure reported by a hardware run in the trace.
+    ///
+    /// The engine only records the first failure. If `BIST_FAIL_CYCLE` does
+    /// not fall on a compared read of the trace, the failure is reported
+    /// without a location.
+    pub fn errors(
+        &self,
+        topology: &SramTopology,
+        outcome: &BistOutcome,
+    ) -> Result<(), BistFailure> {
+        if !outcome.fail {
+            return Ok(());
+        }
+        let dmask = u64::MAX >> (64 - topology.size().width());
+        let located =
+            self.at_cycle(outcome.fail_cycle as u64)
+                .and_then(|(idx, traced)| match traced.op {
+                    FixedSramOp::Read { addr, .. } => Some(BistError::new(
+                        idx,
+                        addr,
+                        outcome.expected,
+                        outcome.received,
+                        dmask,
+                    )),
+                    _ => None,
+                });
+        Err(BistFailure {
+            outcome: *outcome,
+            located,
+        })
+    }
+}
+
+/// A failing BIST run.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct BistFailure {
+    /// The results read back from the engine.
+    pub outcome: BistOutcome,
+    /// The failing read, as located by the model from `BIST_FAIL_CYCLE`, or
+    /// `None` if the cycle does not fall on a compared read. The location
+    /// is only as accurate as the model.
+    pub located: Option<BistError>,
+}
+
+impl fmt::Display for BistFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "BIST failed in cycle {}: got {:#x}, expected {:#x}",
+            self.outcome.fail_cycle, self.outcome.received, self.outcome.expected
+        )?;
+        match &self.located {
+            Some(err) => write!(f, " (model locates op {} at {:#x})", err.op(), err.addr()),
+            None => write!(f, " (model has no compared read in that cycle)"),
+        }
+    }
+}
+
+impl std::error::Error for BistFailure {}
+
+/// The results of a BIST run, as predicted by the model.
+#[derive(Clone, Debug)]
+pub struct BistSimulation {
+    pub outcome: BistOutcome,
+    pub trace: BistTrace,
+}
+
+/// Runs `program` on `ex` the way the model of the BIST engine would.
+///
+/// The outcome is a prediction of the unverified model; see the module
+/// documentation. Memories that do not support reading unwritten words should be
+/// initialized by the program before any random ops.
+pub fn simulate<E: Executor>(
+    program: &BistProgram,
+    topology: &SramTopology,
+    config: &BistRunConfig,
+    ex: &mut E,
+) -> BistSimulation {
+    assert_eq!(
+        (topology.rows() - 1, topology.mux() - 1),
+        (program.max_row_addr, program.max_col_addr),
+        "topology does not match program"
+    );
+    let size = topology.size();
+    let dmask = u64::MAX >> (64 - size.width());
+    let mask_mask = u64::MAX >> (64 - size.mask_width());
+    let depth = program.depth();
+    let table = |source: BistSource, invert: bool, random: u64| -> SramWord {
+        match source {
+            BistSource::Table(idx) => {
+                let word = program.table.get(idx as usize).copied().unwrap_or(0);
+                if invert {
+                    !word
+                } else {
+                    word
+                }
+            }
+            BistSource::Rand => random,
+        }
+    };
+
+    let mut lfsr = Lfsr::new(config.rand_seed);
+    let mut misr = Misr::new(config.sig_seed);
+    let mut trace = BistTrace::default();
+    let mut outcome = BistOutcome {
+        fail: false,
+        fail_cycle: 0,
+        expected: 0,
+        received: 0,
+        signature: 0,
+    };
+    let limit = config.cycle_limit as u64;
+    let mut cycle = 0;
+
+    ex.init();
+    'run: for (element, elt) in program.elements.iter().enumerate() {
+        for i in 0..depth {
+            let mut addr = None;
+            for op in elt.ops.iter() {
+                if cycle >= limit {
+                    break 'run;
+                }
+                lfsr.step();
+                let addr = *addr.get_or_insert_with(|| {
+                    if elt.rand_addr {
+                        let row = lfsr.field(65, 10) as u32 % (program.max_row_addr + 1);
+                        let col = lfsr.field(75, 3) as u32 % (program.max_col_addr + 1);
+                        topology.addr(row, col)
+                    } else {
+                        let i = if elt.descending { depth - 1 - i } else { i };
+                        sweep_addr(program, topology, i)
+                    }
+                });
+                let data = lfsr.field(0, 32);
+                let mask = lfsr.field(32, 32);
+                let fixed = fix_op(op, addr, &table, data, mask, lfsr.field(64, 1));
+                let fixed = match fixed {
+                    FixedSramOp::Write { addr, data, mask } => FixedSramOp::Write {
+                        addr,
+                        data: data & dmask,
+                        mask: mask & mask_mask,
+                    },
+                    FixedSramOp::Read {
+                        addr,
+                        data,
+                        compare,
+                    } => FixedSramOp::Read {
+                        addr,
+                        data: data & dmask,
+                        compare: compare & dmask,
+                    },
+                    op => op,
+                };
+                trace.ops.push(TracedOp {
+                    cycle,
+                    element,
+                    op: fixed,
+                    random: elt.rand_addr || op.is_random(),
+                });
+                match fixed {
+                    FixedSramOp::Write { addr, data, mask } => ex.write(addr, data, mask),
+                    FixedSramOp::Read {
+                        addr,
+                        data,
+                        compare,
+                    } => {
+                        let dout = ex.read(addr) & dmask;
+                        misr.fold(dout as u32);
+                        if (dout ^ data) & compare != 0 && !outcome.fail {
+                            outcome.fail = true;
+                            outcome.fail_cycle = cycle as u32;
+                            outcome.expected = data;
+                            outcome.received = dout;
+                        }
+                        if outcome.fail && config.stop_on_failure {
+                            break 'run;
+                        }
+                    }
+                    FixedSramOp::Capture { addr } => {
+                        misr.fold((ex.read(addr) & dmask) as u32);
+                    }
+                    _ => unreachable!(),
+                }
+                cycle += 1;
+            }
+        }
+    }
+    ex.finish();
+
+    outcome.signature = misr.signature();
+    BistSimulation { outcome, trace }
+}
+
+/// The address visited at position `i` of a sweep.
+fn sweep_addr(program: &BistProgram, topology: &SramTopology, i: SramAddr) -> SramAddr {
+    match program.inner_dim {
+        InnerDim::Col => topology.addr(i / topology.mux(), i % topology.mux()),
+        InnerDim::Row => topology.addr(i % topology.rows(), i / topology.rows()),
+    }
+}
+
+/// Resolves an op, given the random fields drawn for it.
+///
+/// Random reads are not compared, so they are traced as captures.
+fn fix_op(
+    op: &BistOp,
+    addr: SramAddr,
+    table: &impl Fn(BistSource, bool, u64) -> SramWord,
+    rand_data: u64,
+    rand_mask: u64,
+    rand_write: u64,
+) -> FixedSramOp {
+    match op.op_type {
+        BistOpType::Read => FixedSramOp::Read {
+            addr,
+            data: table(op.data, op.invert, rand_data),
+            compare: u64::MAX,
+        },
+        BistOpType::Write => FixedSramOp::Write {
+            addr,
+            data: table(op.data, op.invert, rand_data),
+            mask: table(op.mask, false, rand_mask),
+        },
+        BistOpType::Rand => {
+            if rand_write != 0 {
+                FixedSramOp::Write {
+                    addr,
+                    data: rand_data,
+                    mask: table(op.mask, false, rand_mask),
+                }
+            } else {
+                FixedSramOp::Capture { addr }
+            }
+        }
+    }
+}
@@ -1,8 +1,23 @@
 pub mod bebe;
+#[cfg(feature = "unverified-bist")]
+pub mod bist;
+#[cfg(feature = "tsi")]
+pub mod bringup;
+pub mod coverage;
+#[cfg(feature = "unverified-bist")]
+pub mod crosscheck;
 pub mod executor;
+pub mod fault;
+pub mod generate;
+#[cfg(feature = "unverified-bist")]
+pub mod golden;
+pub mod mmio;
 pub mod pattern;
+pub mod powerup;
+pub mod regs;
 pub mod state;
 pub mod testsite;
+pub mod topology;
 
 #[cfg(test)]
 mod tests;
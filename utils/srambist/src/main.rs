@@ -1,3 +1,105 @@
-fn main() {
-    println!("Hello, world!");
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+use serialport::SerialPort;
+
+use srambist::bebe::BebeUart;
+use srambist::bringup::{BoardRevision, Bringup, BringupSequence};
+use tsi::stac::StacController;
+use tsi::Tsi;
+
+#[derive(Debug, Parser)]
+#[clap(name = "srambist", version)]
+pub struct Args {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Powers up the STAC board and checks that the chip responds.
+    Bringup {
+        /// The FPGA's TSI TTY. Defaults to the socat proxy described in the
+        /// README, which avoids the UART reset issue of the raw TTY.
+        #[clap(long, default_value = "/tmp/ttyFPGA")]
+        fpga: String,
+        /// The baud rate of the FPGA's TSI TTY.
+        #[clap(long)]
+        fpga_baud: u32,
+        /// The chip's bebe UART TTY.
+        #[clap(long, default_value = "/dev/ttyUSB2")]
+        uart: String,
+        /// The baud rate of the chip's bebe UART TTY.
+        #[clap(long)]
+        uart_baud: u32,
+        /// The board revision, which selects the bring-up sequence.
+        #[clap(long, default_value = "stac-v1")]
+        rev: BoardRevision,
+        /// A sequence file to run instead of the sequence of `--rev`; see
+        /// `BringupSequence` for the format.
+        #[clap(long, conflicts_with = "rev")]
+        sequence: Option<PathBuf>,
+    },
+}
+
+/// Opens a TTY whose reads fail with [`std::io::ErrorKind::TimedOut`] after
+/// `timeout`.
+fn open(path: &str, baud: u32, timeout: Duration) -> Box<dyn SerialPort> {
+    serialport::new(path, baud)
+        .timeout(timeout)
+        .open()
+        .unwrap_or_else(|err| panic!("failed to open {path}: {err}"))
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    match args.command {
+        Command::Bringup {
+            fpga,
+            fpga_baud,
+            uart,
+            uart_baud,
+            rev,
+            sequence,
+        } => {
+            let sequence = match sequence {
+                Some(path) => {
+                    let file = fs::read_to_string(&path)
+                        .unwrap_or_else(|err| panic!("failed to read {}: {err}", path.display()));
+                    file.parse::<BringupSequence>()
+                        .unwrap_or_else(|err| panic!("invalid sequence {}: {err}", path.display()))
+                }
+                None => rev.sequence(),
+            };
+            // No single read may outlast the shortest step, so that a silent
+            // FPGA fails the step it hangs instead of blocking.
+            let timeout = sequence
+                .steps
+                .iter()
+                .map(|&(_, timeout)| timeout)
+                .min()
+                .unwrap_or(Duration::from_secs(1));
+            let controller = StacController::new(Tsi::new(open(&fpga, fpga_baud, timeout)));
+            let uart = open(&uart, uart_baud, timeout);
+            let reader = uart.try_clone().expect("failed to clone UART handle");
+            let mut bringup = Bringup::new(controller, BebeUart::new(reader, uart), sequence);
+            match bringup.run() {
+                Ok(()) => {
+                    let (mut controller, _) = bringup.into_inner();
+                    let status = controller.status().expect("failed to read StacController");
+                    print!("{status}");
+                    println!("Board is up");
+                    ExitCode::SUCCESS
+                }
+                Err(err) => {
+                    println!("Bring-up failed: {err}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+    }
 }
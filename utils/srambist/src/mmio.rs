@@ -0,0 +1,543 @@
+//! Driver for the SramBist peripheral, and executors built on it.
+//!
+//! The driver works over any [`Transport`], such as bebe or TSI, and
+//! shadows the configuration registers so that only changed values are
+//! written. Every host access is slow, so this roughly halves the cost of
+//! each SRAM operation.
+
+use crate::executor::Executor;
+use crate::pattern::{SramAddr, SramWord};
+use crate::regs::{sram_bist, Reg};
+#[cfg(feature = "unverified-bist")]
+use crate::{
+    bist::{
+        compile, BistCompileError, BistOutcome, BistProgram, BistRegisters, BistRunConfig,
+        RAND_SEED_BITS,
+    },
+    crosscheck::{CrossCheck, RandomElementError},
+    executor::TestPatternErrors,
+    fault::FaultyExecutor,
+    golden::{simulate, BistFailure, BistSimulation},
+    pattern::Pattern,
+    topology::SramTopology,
+};
+use bitvec::prelude::*;
+use std::collections::HashMap;
+#[cfg(feature = "unverified-bist")]
+use std::fmt;
+use std::io;
+#[cfg(feature = "tsi")]
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+#[cfg(all(feature = "tsi", feature = "unverified-bist"))]
+use tsi::stac::{SramMode, StacController};
+
+/// A way of accessing the chip's memory map.
+pub trait Transport {
+    /// Reads the 64-bit word at `addr`.
+    fn read(&mut self, addr: u64) -> io::Result<u64>;
+    /// Writes the 64-bit word at `addr`.
+    fn write(&mut self, addr: u64, data: u64) -> io::Result<()>;
+}
+
+#[cfg(feature = "tsi")]
+impl<T: Read + Write> Transport for tsi::Tsi<T> {
+    fn read(&mut self, addr: u64) -> io::Result<u64> {
+        self.read_u64(addr)
+    }
+
+    fn write(&mut self, addr: u64, data: u64) -> io::Result<()> {
+        self.write_u64(addr, data)
+    }
+}
+
+/// The circuit driving the test SRAMs, selected by `SRAM_SEL`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SramDriver {
+    /// Single operations issued through `ADDR`, `DIN`, `MASK` and `WE`.
+    Mmio,
+    /// The BIST engine.
+    Bist,
+}
+
+/// The SramBist peripheral.
+///
+/// Register writes are shadowed, and skipped if the register already holds
+/// the value. The shadow assumes this driver is the only one accessing the
+/// peripheral; call [`SramBist::invalidate`] if anything else may have.
+pub struct SramBist<T> {
+    transport: T,
+    /// The last value written to each word, by address.
+    shadow: HashMap<u64, u64>,
+    /// How long an operation or BIST run may take to raise `DONE`.
+    timeout: Duration,
+}
+
+impl<T: Transport> SramBist<T> {
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            shadow: HashMap::new(),
+            timeout: Duration::from_secs(3),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    /// Forgets the shadowed register values, so that the next write to each
+    /// register reaches the hardware.
+    pub fn invalidate(&mut self) {
+        self.shadow.clear();
+    }
+
+    /// Writes `value` to `reg`, skipping words that already hold their value.
+    ///
+    /// Writes to `EX` always reach the hardware.
+    pub fn write_reg(&mut self, reg: Reg, value: &BitSlice<u64>) -> io::Result<()> {
+        let trigger = reg == sram_bist::EX;
+        for (addr, word) in reg.word_addrs().zip(reg.pack(value)) {
+            if trigger || self.shadow.get(&addr) != Some(&word) {
+                // A failed write leaves the register unknown.
+                self.shadow.remove(&addr);
+                self.transport.write(addr, word)?;
+                if !trigger {
+                    self.shadow.insert(addr, word);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the value of `reg` from the hardware.
+    pub fn read_reg(&mut self, reg: Reg) -> io::Result<BitVec<u64>> {
+        let words = reg
+            .word_addrs()
+            .map(|addr| self.transport.read(addr))
+            .collect::<io::Result<Vec<u64>>>()?;
+        Ok(reg.unpack(&words))
+    }
+
+    pub fn select_sram(&mut self, sram_id: u64) -> io::Result<()> {
+        self.write_reg(sram_bist::SRAM_ID, sram_id.view_bits())
+    }
+
+    pub fn set_driver(&mut self, driver: SramDriver) -> io::Result<()> {
+        let sel = match driver {
+            SramDriver::Mmio => 0u64,
+            SramDriver::Bist => 1,
+        };
+        self.write_reg(sram_bist::SRAM_SEL, sel.view_bits())
+    }
+
+    /// Selects the sense amplifier enable source (`SAE_SEL`).
+    pub fn set_sae_sel(&mut self, sel: u64) -> io::Result<()> {
+        self.write_reg(sram_bist::SAE_SEL, sel.view_bits())
+    }
+
+    /// Sets the sense amplifier enable delay code (`SAE_CTL`).
+    pub fn set_sae_ctl(&mut self, code: u64) -> io::Result<()> {
+        self.write_reg(sram_bist::SAE_CTL, code.view_bits())
+    }
+
+    /// Writes `EX` and waits for `DONE`.
+    ///
+    /// An operation takes a few chip cycles, far less than a host access, so
+    /// `DONE` is usually high again by the first poll and its drop cannot be
+    /// observed. Fails with [`io::ErrorKind::TimedOut`] if `DONE` is not high
+    /// within the timeout.
+    pub fn execute(&mut self) -> io::Result<()> {
+        let deadline = Instant::now() + self.timeout;
+        self.write_reg(sram_bist::EX, u64::MAX.view_bits())?;
+        while !self.read_reg(sram_bist::DONE)?[0] {
+            if Instant::now() >= deadline {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "timed out waiting for DONE",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads `addr` of the selected SRAM.
+    pub fn read(&mut self, addr: SramAddr) -> io::Result<SramWord> {
+        self.set_driver(SramDriver::Mmio)?;
+        self.write_reg(sram_bist::ADDR, (addr as u64).view_bits())?;
+        self.write_reg(sram_bist::WE, 0u64.view_bits())?;
+        self.execute()?;
+        Ok(self.read_reg(sram_bist::DOUT)?.load_le())
+    }
+
+    /// Writes `data` to the lanes of `addr` selected by `mask`.
+    pub fn write(&mut self, addr: SramAddr, data: SramWord, mask: SramWord) -> io::Result<()> {
+        self.set_driver(SramDriver::Mmio)?;
+        self.write_reg(sram_bist::ADDR, (addr as u64).view_bits())?;
+        self.write_reg(sram_bist::DIN, data.view_bits())?;
+        self.write_reg(sram_bist::MASK, mask.view_bits())?;
+        self.write_reg(sram_bist::WE, 1u64.view_bits())?;
+        self.execute()
+    }
+}
+
+#[cfg(feature = "unverified-bist")]
+impl<T: Transport> SramBist<T> {
+    /// Programs a BIST configuration without starting the engine.
+    pub fn load_bist(&mut self, regs: &BistRegisters, config: &BistRunConfig) -> io::Result<()> {
+        self.write_reg(
+            sram_bist::BIST_ELEMENT_SEQUENCE,
+            regs.element_sequence.view_bits(),
+        )?;
+        self.write_reg(
+            sram_bist::BIST_PATTERN_TABLE,
+            regs.pattern_table.view_bits(),
+        )?;
+        self.write_reg(
+            sram_bist::BIST_MAX_ELEMENT_IDX,
+            regs.max_element_idx.view_bits(),
+        )?;
+        self.write_reg(sram_bist::BIST_MAX_ROW_ADDR, regs.max_row_addr.view_bits())?;
+        self.write_reg(sram_bist::BIST_MAX_COL_ADDR, regs.max_col_addr.view_bits())?;
+        self.write_reg(sram_bist::BIST_INNER_DIM, regs.inner_dim.view_bits())?;
+
+        let seed = config.rand_seed & ((1 << RAND_SEED_BITS) - 1);
+        self.write_reg(
+            sram_bist::BIST_RAND_SEED,
+            [seed as u64, (seed >> 64) as u64].view_bits(),
+        )?;
+        self.write_reg(
+            sram_bist::BIST_SIG_SEED,
+            (config.sig_seed as u64).view_bits(),
+        )?;
+        self.write_reg(
+            sram_bist::BIST_CYCLE_LIMIT,
+            (config.cycle_limit as u64).view_bits(),
+        )?;
+        self.write_reg(
+            sram_bist::BIST_STOP_ON_FAILURE,
+            (config.stop_on_failure as u64).view_bits(),
+        )?;
+        Ok(())
+    }
+
+    /// Runs the loaded BIST configuration on the selected SRAM.
+    pub fn run_bist(&mut self) -> io::Result<BistOutcome> {
+        self.set_driver(SramDriver::Bist)?;
+        self.execute()?;
+        self.bist_outcome()
+    }
+
+    /// Reads back the results of the last BIST run.
+    pub fn bist_outcome(&mut self) -> io::Result<BistOutcome> {
+        Ok(BistOutcome {
+            fail: self.read_reg(sram_bist::BIST_FAIL)?[0],
+            fail_cycle: self.read_reg(sram_bist::BIST_FAIL_CYCLE)?.load_le(),
+            expected: self.read_reg(sram_bist::BIST_EXPECTED)?.load_le(),
+            received: self.read_reg(sram_bist::BIST_RECEIVED)?.load_le(),
+            signature: self.read_reg(sram_bist::BIST_SIGNATURE)?.load_le(),
+        })
+    }
+}
+
+/// Issues every operation to a test SRAM through the SramBist registers.
+pub struct MmioExecutor<T> {
+    bist: SramBist<T>,
+    sram_id: u64,
+}
+
+impl<T: Transport> MmioExecutor<T> {
+    pub fn with_driver(bist: SramBist<T>, sram_id: u64) -> Self {
+        Self { bist, sram_id }
+    }
+
+    pub fn into_inner(self) -> SramBist<T> {
+        self.bist
+    }
+}
+
+impl<T: Transport> Executor for MmioExecutor<T> {
+    fn init(&mut self) {
+        self.bist.invalidate();
+        self.bist
+            .select_sram(self.sram_id)
+            .and_then(|()| self.bist.set_sae_sel(0))
+            .unwrap_or_else(|err| panic!("failed to configure SramBist: {err}"));
+    }
+
+    fn read(&mut self, addr: SramAddr) -> SramWord {
+        self.bist
+            .read(addr)
+            .unwrap_or_else(|err| panic!("failed to read {addr:#x} through SramBist: {err}"))
+    }
+
+    fn write(&mut self, addr: SramAddr, data: SramWord, mask: SramWord) {
+        self.bist
+            .write(addr, data, mask)
+            .unwrap_or_else(|err| panic!("failed to write {addr:#x} through SramBist: {err}"));
+    }
+
+    fn finish(&mut self) {}
+}
+
+/// An error running a pattern on the BIST engine.
+#[cfg(feature = "unverified-bist")]
+#[derive(Debug)]
+pub enum BistRunError {
+    /// The pattern cannot run on the BIST engine.
+    Compile(BistCompileError),
+    /// The pattern cannot be cross-checked.
+    Random(RandomElementError),
+    /// The engine could not be configured or read back, or did not finish
+    /// in time.
+    Io(io::Error),
+    /// The engine reported a failure.
+    ///
+    /// `errors` holds the failing read as located by the model, like a run
+    /// over the host path would report it, and is empty if the model has no
+    /// compared read in the failing cycle. `outcome` holds the raw results.
+    Failed {
+        errors: TestPatternErrors,
+        outcome: BistOutcome,
+    },
+}
+
+#[cfg(feature = "unverified-bist")]
+impl fmt::Display for BistRunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BistRunError::Compile(err) => write!(f, "pattern cannot run on BIST engine: {err}"),
+            BistRunError::Random(err) => write!(f, "pattern cannot be cross-checked: {err}"),
+            BistRunError::Io(err) => write!(f, "failed to run BIST: {err}"),
+            BistRunError::Failed { errors, outcome } => {
+                write!(
+                    f,
+                    "BIST failed in cycle {}: got {:#x}, expected {:#x}",
+                    outcome.fail_cycle, outcome.received, outcome.expected
+                )?;
+                match errors.errors.first() {
+                    Some(err) => write!(f, " (model locates op {} at {:#x})", err.op(), err.addr()),
+                    None => write!(f, " (model has no compared read in that cycle)"),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "unverified-bist")]
+impl std::error::Error for BistRunError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BistRunError::Compile(err) => Some(err),
+            BistRunError::Random(err) => Some(err),
+            BistRunError::Io(err) => Some(err),
+            BistRunError::Failed { .. } => None,
+        }
+    }
+}
+
+#[cfg(feature = "unverified-bist")]
+impl From<BistCompileError> for BistRunError {
+    fn from(err: BistCompileError) -> Self {
+        BistRunError::Compile(err)
+    }
+}
+
+#[cfg(feature = "unverified-bist")]
+impl From<io::Error> for BistRunError {
+    fn from(err: io::Error) -> Self {
+        BistRunError::Io(err)
+    }
+}
+
+#[cfg(feature = "unverified-bist")]
+impl From<BistFailure> for BistRunError {
+    fn from(failure: BistFailure) -> Self {
+        BistRunError::Failed {
+            errors: TestPatternErrors {
+                errors: failure.located.into_iter().collect(),
+            },
+            outcome: failure.outcome,
+        }
+    }
+}
+
+/// Runs whole patterns on the SramBist BIST engine at chip speed, instead
+/// of issuing every operation over the host path.
+#[cfg(feature = "unverified-bist")]
+pub struct BistExecutor<T> {
+    bist: SramBist<T>,
+    sram_id: u64,
+    topology: SramTopology,
+    config: BistRunConfig,
+}
+
+#[cfg(feature = "unverified-bist")]
+impl<T: Transport> BistExecutor<T> {
+    /// An executor for the STAC test SRAM with the given `SRAM_ID`.
+    pub fn with_driver(bist: SramBist<T>, sram_id: u64) -> Self {
+        Self {
+            bist,
+            sram_id,
+            topology: SramTopology::stac(sram_id as usize),
+            config: BistRunConfig::default(),
+        }
+    }
+
+    pub fn with_config(mut self, config: BistRunConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn topology(&self) -> &SramTopology {
+        &self.topology
+    }
+
+    pub fn into_inner(self) -> SramBist<T> {
+        self.bist
+    }
+
+    /// Programs `program` into the BIST engine, runs it, and reads back the results.
+    ///
+    /// Fails with [`io::ErrorKind::TimedOut`] if the run does not finish
+    /// within the timeout of the driver.
+    pub fn run(&mut self, program: &BistProgram) -> io::Result<BistOutcome> {
+        self.load(program)?;
+        self.bist.run_bist()
+    }
+
+    /// Programs `program` and the run configuration into the BIST engine
+    /// without starting it.
+    pub fn load(&mut self, program: &BistProgram) -> io::Result<()> {
+        self.bist.select_sram(self.sram_id)?;
+        self.bist.set_sae_sel(0)?;
+        self.bist.load_bist(&program.registers(), &self.config)
+    }
+
+    /// Compiles and runs `pattern` on the BIST engine.
+    ///
+    /// A failing run is reported as [`BistRunError::Failed`], with the raw
+    /// results alongside the errors. Blocked, like the rest of the engine
+    /// support, on a verified register encoding.
+    pub fn execute(&mut self, pattern: &Pattern) -> Result<(), BistRunError> {
+        let (program, expected) = self.prepare(pattern)?;
+        println!("Beginning SRAM BIST test on BIST engine");
+        let outcome = self.run(&program)?;
+        self.report(&outcome, &expected)
+    }
+
+    /// Programs `program` into the BIST engine and runs it by raising
+    /// `SRAM_BIST_START` through `controller`, rather than writing `EX`.
+    ///
+    /// Fails if `SRAM_BIST_DONE` is already high before the start, or with
+    /// [`std::io::ErrorKind::TimedOut`] if it is not raised within `timeout`.
+    /// The SRAMs are returned to internal control either way.
+    #[cfg(feature = "tsi")]
+    pub fn run_external<U: Read + Write>(
+        &mut self,
+        program: &BistProgram,
+        controller: &mut StacController<U>,
+        timeout: Duration,
+    ) -> io::Result<BistOutcome> {
+        self.load(program)?;
+        self.bist.set_driver(SramDriver::Bist)?;
+        controller.set_sram_mode(SramMode::External)?;
+        let done = controller
+            .bist_start()
+            .and_then(|()| controller.wait_bist_done(timeout));
+        controller.bist_stop()?;
+        controller.set_sram_mode(SramMode::Internal)?;
+        done?;
+        self.bist.bist_outcome()
+    }
+
+    /// Compiles and runs `pattern` on the BIST engine, triggered through the
+    /// `StacController` pins.
+    #[cfg(feature = "tsi")]
+    pub fn execute_external<U: Read + Write>(
+        &mut self,
+        pattern: &Pattern,
+        controller: &mut StacController<U>,
+        timeout: Duration,
+    ) -> Result<(), BistRunError> {
+        let (program, expected) = self.prepare(pattern)?;
+        println!("Beginning SRAM BIST test on BIST engine via StacController pins");
+        let outcome = self.run_external(&program, controller, timeout)?;
+        self.report(&outcome, &expected)
+    }
+
+    /// Runs `pattern` both on the BIST engine and over the host path through
+    /// `host`, which must access the same SRAM, and compares the results.
+    ///
+    /// The host replays the ops the model predicts for the BIST engine. Fails
+    /// without running anything if the pattern uses the random generator.
+    pub fn cross_check<E: Executor>(
+        &mut self,
+        pattern: &Pattern,
+        host: E,
+    ) -> Result<CrossCheck, BistRunError> {
+        let (program, expected) = self.prepare(pattern)?;
+        if let Some(element) = program.random_element() {
+            return Err(BistRunError::Random(RandomElementError { element }));
+        }
+        println!("Beginning SRAM BIST cross-check over host path");
+        let host = expected.trace.replay(host);
+        // The host path may have changed registers behind our back.
+        self.bist.invalidate();
+        println!("Beginning SRAM BIST cross-check on BIST engine");
+        let outcome = self.run(&program)?;
+        let check = CrossCheck::new(
+            &self.topology,
+            &expected.trace,
+            host,
+            &outcome,
+            expected.outcome.signature,
+        );
+        print!("{check}");
+        Ok(check)
+    }
+
+    /// Prints the results of a run, and locates any failure in the trace of
+    /// the model.
+    fn report(
+        &self,
+        outcome: &BistOutcome,
+        predicted: &BistSimulation,
+    ) -> Result<(), BistRunError> {
+        println!(
+            "BIST {} (signature {:#x}, model predicts {:#x})",
+            if outcome.fail { "FAILED" } else { "PASSED" },
+            outcome.signature,
+            predicted.outcome.signature
+        );
+        let result = predicted.trace.errors(&self.topology, outcome);
+        if let Err(failure) = &result {
+            println!("{failure}");
+        }
+        Ok(result?)
+    }
+
+    /// Compiles `pattern` and predicts the results of running it on an ideal SRAM.
+    fn prepare(
+        &self,
+        pattern: &Pattern,
+    ) -> Result<(BistProgram, BistSimulation), BistCompileError> {
+        let program = compile(pattern, &self.topology)?;
+        let expected = simulate(
+            &program,
+            &self.topology,
+            &self.config,
+            &mut FaultyExecutor::new(self.topology.size(), vec![]),
+        );
+        Ok((program, expected))
+    }
+}
@@ -1,6 +1,9 @@
 use crate::state::SramState;
+use crate::topology::SramTopology;
 use rand_chacha::rand_core::{RngCore, SeedableRng};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
 
 pub type SramWord = u64;
 pub type SramAddr = u32;
@@ -11,40 +14,512 @@ pub type SramAddr = u32;
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct Pattern {
+    pub(crate) elements: Vec<Element>,
+}
+
+/// Builds a [`Pattern`] element by element.
+///
+/// ```
+/// # use srambist::pattern::{Pattern, SramInput, SramOp};
+/// let mats_plus = Pattern::builder()
+///     .up([SramOp::write(SramInput::Fixed(0))])
+///     .up([SramOp::Read, SramOp::write(SramInput::Fixed(u64::MAX))])
+///     .down([SramOp::Read, SramOp::write(SramInput::Fixed(0))])
+///     .build();
+/// assert_eq!(mats_plus, Pattern::mats_plus());
+/// ```
+#[derive(Clone, Default, Debug)]
+pub struct PatternBuilder {
     elements: Vec<Element>,
 }
 
+impl PatternBuilder {
+    pub fn element(mut self, element: Element) -> Self {
+        self.elements.push(element);
+        self
+    }
+
+    /// Appends an element applying `ops` in ascending address order.
+    pub fn up(self, ops: impl IntoIterator<Item = SramOp>) -> Self {
+        self.element(Element::new(AddrSeq::Up, ops))
+    }
+
+    /// Appends an element applying `ops` in descending address order.
+    pub fn down(self, ops: impl IntoIterator<Item = SramOp>) -> Self {
+        self.element(Element::new(AddrSeq::Down, ops))
+    }
+
+    /// Appends an element holding the array idle for `duration`.
+    pub fn pause(self, duration: Duration) -> Self {
+        self.element(Element::pause(duration))
+    }
+
+    /// Appends every element of `pattern`.
+    pub fn pattern(mut self, pattern: Pattern) -> Self {
+        self.elements.extend(pattern.elements);
+        self
+    }
+
+    pub fn build(self) -> Pattern {
+        Pattern {
+            elements: self.elements,
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 pub struct Element {
-    ops: Vec<SramOp>,
-    addr_seq: AddrSeq,
+    pub(crate) ops: Vec<SramOp>,
+    pub(crate) addr_seq: AddrSeq,
+    #[serde(default)]
+    pub(crate) scope: AddrScope,
+}
+
+/// The set of addresses visited by an [`Element`].
+///
+/// The element's [`AddrSeq`] determines the order in which they are visited.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum AddrScope {
+    /// Every address in the SRAM.
+    #[default]
+    Full,
+    /// Addresses from `start` (inclusive) to `end` (exclusive).
+    Range { start: SramAddr, end: SramAddr },
+    /// An explicit set of addresses.
+    List(Vec<SramAddr>),
+}
+
+impl Element {
+    /// An element applying `ops` at every address, visited in the order given by `addr_seq`.
+    pub fn new(addr_seq: AddrSeq, ops: impl IntoIterator<Item = SramOp>) -> Self {
+        Self {
+            ops: ops.into_iter().collect(),
+            addr_seq,
+            scope: AddrScope::Full,
+        }
+    }
+
+    pub fn with_scope(mut self, scope: AddrScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    pub fn ops(&self) -> &[SramOp] {
+        &self.ops
+    }
+    pub fn addr_seq(&self) -> AddrSeq {
+        self.addr_seq
+    }
+    pub fn scope(&self) -> &AddrScope {
+        &self.scope
+    }
+
+    /// An element that holds the array idle for the given duration.
+    pub fn pause(duration: Duration) -> Self {
+        Self {
+            ops: vec![SramOp::Pause(duration)],
+            addr_seq: AddrSeq::Up,
+            scope: AddrScope::Full,
+        }
+    }
+
+    /// Whether this element consists only of pauses.
+    pub fn is_pause(&self) -> bool {
+        !self.ops.is_empty() && self.ops.iter().all(|op| matches!(op, SramOp::Pause(_)))
+    }
+}
+
+impl AddrScope {
+    pub fn contains(&self, addr: SramAddr) -> bool {
+        match self {
+            AddrScope::Full => true,
+            AddrScope::Range { start, end } => (*start..*end).contains(&addr),
+            AddrScope::List(addrs) => addrs.contains(&addr),
+        }
+    }
+
+    /// The in-bounds addresses in this scope, in ascending order.
+    pub fn addrs(&self, depth: SramAddr) -> Vec<SramAddr> {
+        match self {
+            AddrScope::Full => (0..depth).collect(),
+            AddrScope::Range { start, end } => (*start..(*end).min(depth)).collect(),
+            AddrScope::List(addrs) => {
+                let mut addrs: Vec<SramAddr> =
+                    addrs.iter().copied().filter(|&addr| addr < depth).collect();
+                addrs.sort_unstable();
+                addrs.dedup();
+                addrs
+            }
+        }
+    }
+
+    /// The first address in this scope that lies outside an SRAM of the given depth.
+    fn out_of_bounds(&self, depth: SramAddr) -> Option<SramAddr> {
+        match self {
+            AddrScope::Full => None,
+            AddrScope::Range { start, end } => (*end > depth).then_some((*start).max(depth)),
+            AddrScope::List(addrs) => addrs.iter().copied().find(|&addr| addr >= depth),
+        }
+    }
+}
+
+/// An error found while validating a [`FixedPattern`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum PatternError {
+    /// An element's scope includes an address outside the SRAM.
+    OutOfBounds { element: usize, addr: SramAddr },
+    /// An element may read an address none of whose mask lanes have been written.
+    UninitializedRead { element: usize, addr: SramAddr },
+    /// An element repeats an operation zero times.
+    ZeroRepeat { element: usize },
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::OutOfBounds { element, addr } => {
+                write!(
+                    f,
+                    "element {element} addresses {addr:#x}, which is out of bounds"
+                )
+            }
+            PatternError::UninitializedRead { element, addr } => write!(
+                f,
+                "element {element} reads {addr:#x}, which has not been initialized"
+            ),
+            PatternError::ZeroRepeat { element } => {
+                write!(f, "element {element} repeats an operation zero times")
+            }
+        }
+    }
 }
 
+impl std::error::Error for PatternError {}
+
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 pub enum AddrSeq {
     Up,
     Down,
     /// Try the given number of random addresses
     Rand(u64),
+    /// Try `n` random addresses drawn from `dist`, then visit every address
+    /// in scope that was missed if `cover_all` is set.
+    RandDist {
+        n: u64,
+        dist: AddrDist,
+        cover_all: bool,
+    },
+    /// Alternate between an address and its mirror from the end of the
+    /// array: `0, depth - 1, 1, depth - 2, ...`.
+    ///
+    /// For power-of-two depths, the mirror is the bitwise complement of the
+    /// address.
+    Complement {
+        descending: bool,
+    },
+    /// Visit addresses in Gray code order.
+    ///
+    /// For power-of-two depths, consecutive addresses differ in a single bit.
+    /// Otherwise, the codes of the next power of two that are out of range
+    /// are skipped, so some consecutive addresses differ in several bits.
+    /// Codes outside the element's scope are skipped in the same way.
+    Gray {
+        descending: bool,
+    },
+    /// Increment the address by `2^log2_stride`, wrapping around to the next
+    /// offset once the end of the array is reached.
+    Stride {
+        log2_stride: u32,
+        descending: bool,
+    },
+    /// Sweep the rows of each column before moving to the next column
+    /// (`BIST_INNER_DIM` = row).
+    ///
+    /// Requires the [`FixedPattern`] to have an [`SramTopology`].
+    RowFast {
+        descending: bool,
+    },
+    /// Sweep the columns of each row before moving to the next row
+    /// (`BIST_INNER_DIM` = column).
+    ///
+    /// Requires the [`FixedPattern`] to have an [`SramTopology`].
+    ColFast {
+        descending: bool,
+    },
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 pub enum SramOp {
+    Read,
+    /// Read the current address, comparing only the bits set in `compare`.
+    ReadMasked {
+        compare: SramWord,
+    },
+    Write {
+        data: SramInput,
+        mask: SramInput,
+    },
+    Rand {
+        mask: RandMask,
+    },
+    /// A random read or write, chosen with relative weights `reads` and `writes`.
+    RandAccess {
+        reads: u32,
+        writes: u32,
+        mask: RandMask,
+        data: DataDist,
+    },
+    /// Hold the array idle for the given duration.
+    ///
+    /// In an element consisting only of pauses, the pauses are applied once
+    /// rather than at every address.
+    Pause(Duration),
+    /// Read the current address and record the value without comparing it.
+    ///
+    /// Unlike [`SramOp::Read`], capturing an address that was never written is legal.
+    Capture,
+    /// Apply `op` `count` times in a row to the current address (e.g. `r0^n`).
+    Repeat {
+        op: RepeatOp,
+        count: u32,
+    },
+    /// Apply `op` `count` times in a row to each address with a cell physically
+    /// adjacent to the current address.
+    ///
+    /// Requires the [`FixedPattern`] to have an [`SramTopology`].
+    Hammer {
+        op: RepeatOp,
+        count: u32,
+    },
+}
+
+/// An operation that can be repeated by [`SramOp::Repeat`] and [`SramOp::Hammer`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum RepeatOp {
     Read,
     Write { data: SramInput, mask: SramInput },
-    Rand { mask: RandMask },
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 pub enum RandMask {
     Fixed(SramWord),
     Rand,
+    /// A single randomly chosen mask lane.
+    SingleLane,
+}
+
+/// The distribution of addresses drawn by [`AddrSeq::RandDist`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum AddrDist {
+    /// Every address in scope is equally likely.
+    Uniform,
+    /// `percent`% of accesses fall uniformly in `start..end`; the rest are
+    /// uniform over the whole scope.
+    HotSpot {
+        start: SramAddr,
+        end: SramAddr,
+        percent: u32,
+    },
+    /// Each address is within `max_step` positions of the previous one.
+    Locality { max_step: u32 },
+    /// Addresses within `radius` of `addr`.
+    Neighbourhood { addr: SramAddr, radius: u32 },
+}
+
+/// The distribution of data written by [`SramOp::RandAccess`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum DataDist {
+    /// Every word is equally likely.
+    Uniform,
+    /// Words with at most `bits` randomly chosen bits set.
+    Sparse { bits: u32 },
+    /// `base` with `flips` randomly chosen bits flipped, e.g. a physical
+    /// background with a few disturbed cells.
+    Relative { base: SramInput, flips: u32 },
+}
+
+/// Parameters for [`Pattern::rand_with`].
+///
+/// [`RandConfig::new`] gives the behaviour of [`Pattern::rand`]: an even
+/// mix of reads and full-mask writes of uniform data to uniform addresses.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub struct RandConfig {
+    /// The number of random accesses.
+    pub accesses: u64,
+    /// The relative weight of reads among accesses.
+    pub reads: u32,
+    /// The relative weight of writes among accesses.
+    pub writes: u32,
+    pub mask: RandMask,
+    pub addrs: AddrDist,
+    pub data: DataDist,
+    /// Whether to follow the random accesses with one access to every address
+    /// in scope that was not visited.
+    pub cover_all: bool,
+}
+
+impl RandConfig {
+    pub fn new(accesses: u64) -> Self {
+        Self {
+            accesses,
+            reads: 1,
+            writes: 1,
+            mask: RandMask::Fixed(u64::MAX),
+            addrs: AddrDist::Uniform,
+            data: DataDist::Uniform,
+            cover_all: false,
+        }
+    }
+
+    /// Whether the configuration can be expressed with [`AddrSeq::Rand`] and [`SramOp::Rand`].
+    fn is_uniform(&self) -> bool {
+        self.reads == 1
+            && self.writes == 1
+            && self.addrs == AddrDist::Uniform
+            && self.data == DataDist::Uniform
+            && !self.cover_all
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 pub enum SramInput {
     Fixed(SramWord),
     Rand,
+    /// A physical data background, evaluated per address.
+    ///
+    /// Requires the [`FixedPattern`] to have an [`SramTopology`].
+    /// Only valid as write data.
+    Background {
+        kind: Background,
+        inverted: bool,
+    },
+    /// The address being written.
+    Addr,
+    /// The bitwise inverse of the address being written.
+    InvAddr,
+    /// A hash of the address being written, using the given seed.
+    AddrHash(u64),
+}
+
+impl SramOp {
+    /// A full-word write of `data`.
+    pub fn write(data: SramInput) -> Self {
+        SramOp::Write {
+            data,
+            mask: SramInput::Fixed(u64::MAX),
+        }
+    }
+
+    /// The write data of this operation, if any.
+    fn data(&self) -> Option<SramInput> {
+        match *self {
+            SramOp::Write { data, .. }
+            | SramOp::Repeat {
+                op: RepeatOp::Write { data, .. },
+                ..
+            }
+            | SramOp::Hammer {
+                op: RepeatOp::Write { data, .. },
+                ..
+            } => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Applies `f` to the write data of this operation, if any.
+    fn map_data(&mut self, f: &mut impl FnMut(SramInput) -> SramInput) {
+        match self {
+            SramOp::Write { data, .. }
+            | SramOp::Repeat {
+                op: RepeatOp::Write { data, .. },
+                ..
+            }
+            | SramOp::Hammer {
+                op: RepeatOp::Write { data, .. },
+                ..
+            } => *data = f(*data),
+            _ => {}
+        }
+    }
+
+    /// Applies `f` to the write mask of this operation, if any.
+    fn map_mask(&mut self, f: &mut impl FnMut(SramInput) -> SramInput) {
+        match self {
+            SramOp::Write { mask, .. }
+            | SramOp::Repeat {
+                op: RepeatOp::Write { mask, .. },
+                ..
+            }
+            | SramOp::Hammer {
+                op: RepeatOp::Write { mask, .. },
+                ..
+            } => *mask = f(*mask),
+            _ => {}
+        }
+    }
+}
+
+impl SramInput {
+    /// The bitwise inverse of this input.
+    ///
+    /// Random inputs ([`SramInput::Rand`] and [`SramInput::AddrHash`]) are
+    /// returned unchanged, since inverting them does not change their distribution.
+    pub fn inverted(self) -> Self {
+        match self {
+            SramInput::Fixed(data) => SramInput::Fixed(!data),
+            SramInput::Background { kind, inverted } => SramInput::Background {
+                kind,
+                inverted: !inverted,
+            },
+            SramInput::Addr => SramInput::InvAddr,
+            SramInput::InvAddr => SramInput::Addr,
+            SramInput::Rand | SramInput::AddrHash(_) => self,
+        }
+    }
+
+    /// Evaluates an address-dependent input for the given address, returning
+    /// `None` for inputs that do not depend only on the address.
+    pub fn addr_value(&self, addr: SramAddr) -> Option<SramWord> {
+        match *self {
+            SramInput::Addr => Some(addr as SramWord),
+            SramInput::InvAddr => Some(!(addr as SramWord)),
+            SramInput::AddrHash(seed) => Some(addr_hash(addr, seed)),
+            _ => None,
+        }
+    }
+
+    /// Decodes the address whose data under this input is `data`.
+    ///
+    /// Returns `None` if the input is not address-dependent, or if no
+    /// single address of an SRAM of the given size maps to `data`.
+    pub fn owner(&self, data: SramWord, size: SramSize) -> Option<SramAddr> {
+        let dmask = u64::MAX >> (64 - size.width);
+        let mut owners = (0..size.depth)
+            .filter(|&addr| self.addr_value(addr).map(|value| value & dmask) == Some(data));
+        match (owners.next(), owners.next()) {
+            (Some(addr), None) => Some(addr),
+            _ => None,
+        }
+    }
+}
+
+/// A SplitMix64 hash of `addr`.
+fn addr_hash(addr: SramAddr, seed: u64) -> SramWord {
+    let mut z = seed.wrapping_add((addr as u64 + 1).wrapping_mul(0x9e3779b97f4a7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// A data background defined over the physical cell array.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum Background {
+    /// Cells alternate between 0 and 1 along both rows and columns.
+    Checkerboard,
+    /// Cells alternate between 0 and 1 from row to row.
+    RowStripe,
+    /// Cells alternate between 0 and 1 from column to column.
+    ColStripe,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
@@ -55,11 +530,26 @@ pub struct SramSize {
 }
 
 impl Pattern {
+    /// Captures every address without writing anything, recording the
+    /// power-up state of the array.
+    ///
+    /// See [`crate::powerup`] for analysis of the captured values.
+    pub fn power_up() -> Self {
+        Self {
+            elements: vec![Element {
+                addr_seq: AddrSeq::Up,
+                scope: AddrScope::Full,
+                ops: vec![SramOp::Capture],
+            }],
+        }
+    }
+
     pub fn mats_plus() -> Self {
         Self {
             elements: vec![
                 Element {
                     addr_seq: AddrSeq::Up,
+                    scope: AddrScope::Full,
                     ops: vec![SramOp::Write {
                         data: SramInput::Fixed(0),
                         mask: SramInput::Fixed(u64::MAX),
@@ -67,6 +557,7 @@ impl Pattern {
                 },
                 Element {
                     addr_seq: AddrSeq::Up,
+                    scope: AddrScope::Full,
                     ops: vec![
                         SramOp::Read,
                         SramOp::Write {
@@ -77,6 +568,7 @@ impl Pattern {
                 },
                 Element {
                     addr_seq: AddrSeq::Down,
+                    scope: AddrScope::Full,
                     ops: vec![
                         SramOp::Read,
                         SramOp::Write {
@@ -94,6 +586,7 @@ impl Pattern {
             elements: vec![
                 Element {
                     addr_seq: AddrSeq::Up,
+                    scope: AddrScope::Full,
                     ops: vec![SramOp::Write {
                         data: SramInput::Fixed(0),
                         mask: SramInput::Fixed(u64::MAX),
@@ -101,6 +594,7 @@ impl Pattern {
                 },
                 Element {
                     addr_seq: AddrSeq::Up,
+                    scope: AddrScope::Full,
                     ops: vec![
                         SramOp::Read,
                         SramOp::Write {
@@ -111,6 +605,7 @@ impl Pattern {
                 },
                 Element {
                     addr_seq: AddrSeq::Up,
+                    scope: AddrScope::Full,
                     ops: vec![
                         SramOp::Read,
                         SramOp::Write {
@@ -121,6 +616,7 @@ impl Pattern {
                 },
                 Element {
                     addr_seq: AddrSeq::Down,
+                    scope: AddrScope::Full,
                     ops: vec![
                         SramOp::Read,
                         SramOp::Write {
@@ -131,6 +627,7 @@ impl Pattern {
                 },
                 Element {
                     addr_seq: AddrSeq::Down,
+                    scope: AddrScope::Full,
                     ops: vec![
                         SramOp::Read,
                         SramOp::Write {
@@ -141,17 +638,52 @@ impl Pattern {
                 },
                 Element {
                     addr_seq: AddrSeq::Up,
+                    scope: AddrScope::Full,
                     ops: vec![SramOp::Read],
                 },
             ],
         }
     }
 
+    /// An initializing write and read of zeros, then `n` random accesses.
+    ///
+    /// Equivalent to `Pattern::rand_with(&RandConfig::new(n))`.
     pub fn rand(n: u64) -> Self {
+        Self::rand_with(&RandConfig::new(n))
+    }
+
+    /// An initializing write and read of zeros, then random accesses as
+    /// described by `config`.
+    pub fn rand_with(config: &RandConfig) -> Self {
+        assert!(
+            config.reads + config.writes > 0,
+            "reads and writes cannot both have zero weight"
+        );
+        let (addr_seq, op) = if config.is_uniform() {
+            (
+                AddrSeq::Rand(config.accesses),
+                SramOp::Rand { mask: config.mask },
+            )
+        } else {
+            (
+                AddrSeq::RandDist {
+                    n: config.accesses,
+                    dist: config.addrs,
+                    cover_all: config.cover_all,
+                },
+                SramOp::RandAccess {
+                    reads: config.reads,
+                    writes: config.writes,
+                    mask: config.mask,
+                    data: config.data,
+                },
+            )
+        };
         Self {
             elements: vec![
                 Element {
                     addr_seq: AddrSeq::Up,
+                    scope: AddrScope::Full,
                     ops: vec![
                         SramOp::Write {
                             data: SramInput::Fixed(0),
@@ -161,14 +693,513 @@ impl Pattern {
                     ],
                 },
                 Element {
-                    addr_seq: AddrSeq::Rand(n),
-                    ops: vec![SramOp::Rand {
-                        mask: RandMask::Fixed(u64::MAX),
+                    addr_seq,
+                    scope: AddrScope::Full,
+                    ops: vec![op],
+                },
+            ],
+        }
+    }
+
+    /// A data retention test: writes a solid background, waits, then reads it
+    /// back and writes the inverse, waits again, and reads the inverse back.
+    pub fn retention(pause: Duration) -> Self {
+        Self {
+            elements: vec![
+                Element {
+                    addr_seq: AddrSeq::Up,
+                    scope: AddrScope::Full,
+                    ops: vec![SramOp::Write {
+                        data: SramInput::Fixed(0),
+                        mask: SramInput::Fixed(u64::MAX),
                     }],
                 },
+                Element::pause(pause),
+                Element {
+                    addr_seq: AddrSeq::Up,
+                    scope: AddrScope::Full,
+                    ops: vec![
+                        SramOp::Read,
+                        SramOp::Write {
+                            data: SramInput::Fixed(u64::MAX),
+                            mask: SramInput::Fixed(u64::MAX),
+                        },
+                    ],
+                },
+                Element::pause(pause),
+                Element {
+                    addr_seq: AddrSeq::Up,
+                    scope: AddrScope::Full,
+                    ops: vec![SramOp::Read],
+                },
             ],
         }
     }
+
+    /// A disturb test: repeats reads and non-transition writes `count` times
+    /// at each address, then hammers the physical neighbours of each address
+    /// and checks it was not disturbed.
+    ///
+    /// Targets deceptive read destructive and write disturb faults, which a
+    /// single read or a transition write cannot sensitize. Requires the
+    /// [`FixedPattern`] to have an [`SramTopology`].
+    pub fn hammer(count: u32) -> Self {
+        let write = |value: bool| RepeatOp::Write {
+            data: SramInput::Fixed(if value { u64::MAX } else { 0 }),
+            mask: SramInput::Fixed(u64::MAX),
+        };
+        let element = |ops: Vec<SramOp>| Element {
+            addr_seq: AddrSeq::Up,
+            scope: AddrScope::Full,
+            ops,
+        };
+        // ⇑(r^n, w, r, w^n, r): a single non-transition write is checked
+        // before the repeated ones, since a disturb can be undone by the next.
+        let disturb = |value: bool| {
+            element(vec![
+                SramOp::Repeat {
+                    op: RepeatOp::Read,
+                    count,
+                },
+                SramOp::Repeat {
+                    op: write(value),
+                    count: 1,
+                },
+                SramOp::Read,
+                SramOp::Repeat {
+                    op: write(value),
+                    count,
+                },
+                SramOp::Read,
+            ])
+        };
+        let hammer = |op: RepeatOp| element(vec![SramOp::Hammer { op, count }, SramOp::Read]);
+        Self {
+            elements: vec![
+                element(vec![SramOp::Repeat {
+                    op: write(false),
+                    count: 1,
+                }]),
+                disturb(false),
+                hammer(RepeatOp::Read),
+                element(vec![SramOp::Repeat {
+                    op: write(true),
+                    count: 1,
+                }]),
+                disturb(true),
+                hammer(write(true)),
+            ],
+        }
+    }
+
+    /// Writes each address's own index, then its inverse, checking each
+    /// after every address has been written.
+    ///
+    /// A decoder fault that aliases two addresses shows up as a read of the
+    /// other address's index; see [`SramInput::owner`].
+    pub fn address_as_data() -> Self {
+        Self {
+            elements: vec![
+                Element {
+                    addr_seq: AddrSeq::Up,
+                    scope: AddrScope::Full,
+                    ops: vec![SramOp::Write {
+                        data: SramInput::Addr,
+                        mask: SramInput::Fixed(u64::MAX),
+                    }],
+                },
+                Element {
+                    addr_seq: AddrSeq::Up,
+                    scope: AddrScope::Full,
+                    ops: vec![
+                        SramOp::Read,
+                        SramOp::Write {
+                            data: SramInput::InvAddr,
+                            mask: SramInput::Fixed(u64::MAX),
+                        },
+                    ],
+                },
+                Element {
+                    addr_seq: AddrSeq::Down,
+                    scope: AddrScope::Full,
+                    ops: vec![SramOp::Read],
+                },
+            ],
+        }
+    }
+
+    /// Walks a single enabled mask lane across the word on solid 0 and 1
+    /// backgrounds, writing the opposite value through each lane and reading
+    /// the word back.
+    ///
+    /// Detects write mask decode faults that write a lane other than the
+    /// enabled one, or that fail to write the enabled lane.
+    pub fn walking_lanes(size: SramSize) -> Self {
+        Self::lane_pattern((0..size.mask_width()).map(|lane| 1 << lane))
+    }
+
+    /// Like [`Pattern::walking_lanes`], but writes every lane except one.
+    ///
+    /// Detects write mask decode faults that write a disabled lane when
+    /// most lanes are enabled.
+    pub fn complementary_lanes(size: SramSize) -> Self {
+        let mask_mask = u64::MAX >> (64 - size.mask_width());
+        Self::lane_pattern((0..size.mask_width()).map(|lane| !(1 << lane) & mask_mask))
+    }
+
+    /// Initializes the array, then performs `n` random writes with random data
+    /// and random lane masks to random addresses, reading back each word.
+    pub fn rand_lanes(n: u64) -> Self {
+        Self {
+            elements: vec![
+                Element {
+                    addr_seq: AddrSeq::Up,
+                    scope: AddrScope::Full,
+                    ops: vec![SramOp::Write {
+                        data: SramInput::Fixed(0),
+                        mask: SramInput::Fixed(u64::MAX),
+                    }],
+                },
+                Element {
+                    addr_seq: AddrSeq::Rand(n),
+                    scope: AddrScope::Full,
+                    ops: vec![
+                        SramOp::Write {
+                            data: SramInput::Rand,
+                            mask: SramInput::Rand,
+                        },
+                        SramOp::Read,
+                    ],
+                },
+            ],
+        }
+    }
+
+    /// For each background `b` in {0, 1}, writes `b` to the whole array, then
+    /// for each lane mask `m`: `⇑(w!b[m], r, wb[m], r)`.
+    fn lane_pattern(masks: impl Iterator<Item = SramWord> + Clone) -> Self {
+        let solid = |value: bool| SramInput::Fixed(if value { u64::MAX } else { 0 });
+        let mut elements = Vec::new();
+        for background in [false, true] {
+            elements.push(Element {
+                addr_seq: AddrSeq::Up,
+                scope: AddrScope::Full,
+                ops: vec![SramOp::Write {
+                    data: solid(background),
+                    mask: SramInput::Fixed(u64::MAX),
+                }],
+            });
+            for mask in masks.clone() {
+                let mask = SramInput::Fixed(mask);
+                elements.push(Element {
+                    addr_seq: AddrSeq::Up,
+                    scope: AddrScope::Full,
+                    ops: vec![
+                        SramOp::Write {
+                            data: solid(!background),
+                            mask,
+                        },
+                        SramOp::Read,
+                        SramOp::Write {
+                            data: solid(background),
+                            mask,
+                        },
+                        SramOp::Read,
+                    ],
+                });
+            }
+        }
+        Self { elements }
+    }
+
+    /// Restricts every element of the pattern to the given addresses.
+    ///
+    /// Useful for running heavy diagnostic patterns on a small window
+    /// around known failures.
+    pub fn within(mut self, scope: AddrScope) -> Self {
+        for elt in self.elements.iter_mut() {
+            elt.scope = scope.clone();
+        }
+        self
+    }
+
+    /// Expands a bit-oriented march pattern into its word-oriented version.
+    ///
+    /// The pattern is repeated once per [word-oriented data background](SramSize::word_backgrounds),
+    /// with all-zeros write data replaced by the background and all-ones
+    /// write data replaced by its inverse.
+    pub fn word_oriented(&self, size: SramSize) -> Self {
+        let dmask = u64::MAX >> (64 - size.width);
+        let mut elements = Vec::new();
+        for bg in size.word_backgrounds() {
+            for elt in self.elements.iter() {
+                let mut elt = elt.clone();
+                for op in elt.ops.iter_mut() {
+                    if let SramOp::Write { data, .. } = op {
+                        match *data {
+                            SramInput::Fixed(0) => *data = SramInput::Fixed(bg),
+                            SramInput::Fixed(u64::MAX) => *data = SramInput::Fixed(!bg & dmask),
+                            _ => {}
+                        }
+                    }
+                }
+                elements.push(elt);
+            }
+        }
+        Self { elements }
+    }
+
+    /// Replaces solid all-zeros and all-ones write data with the given
+    /// background and its inverse, respectively.
+    ///
+    /// For example, `Pattern::march_cm().with_background(Background::Checkerboard)`
+    /// runs March C- over a physical checkerboard.
+    pub fn with_background(mut self, kind: Background) -> Self {
+        for elt in self.elements.iter_mut() {
+            for op in elt.ops.iter_mut() {
+                if let SramOp::Write { data, .. } = op {
+                    match *data {
+                        SramInput::Fixed(0) => {
+                            *data = SramInput::Background {
+                                kind,
+                                inverted: false,
+                            }
+                        }
+                        SramInput::Fixed(u64::MAX) => {
+                            *data = SramInput::Background {
+                                kind,
+                                inverted: true,
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    /// Starts building a pattern from individual elements.
+    pub fn builder() -> PatternBuilder {
+        PatternBuilder::default()
+    }
+
+    pub fn elements(&self) -> &[Element] {
+        &self.elements
+    }
+
+    /// The address-dependent inputs written by the pattern, in order of
+    /// first use, for decoding which address a misread value belongs to
+    /// (see [`BistError::owner`](crate::executor::BistError::owner)).
+    pub fn addr_inputs(&self) -> Vec<SramInput> {
+        let mut inputs = Vec::new();
+        for input in self
+            .elements
+            .iter()
+            .flat_map(|elt| elt.ops.iter())
+            .filter_map(SramOp::data)
+        {
+            if input.addr_value(0).is_some() && !inputs.contains(&input) {
+                inputs.push(input);
+            }
+        }
+        inputs
+    }
+
+    /// This pattern followed by `other`.
+    pub fn then(mut self, other: Pattern) -> Self {
+        self.elements.extend(other.elements);
+        self
+    }
+
+    /// The given patterns applied one after another.
+    pub fn concat(patterns: impl IntoIterator<Item = Pattern>) -> Self {
+        Self {
+            elements: patterns
+                .into_iter()
+                .flat_map(|pattern| pattern.elements)
+                .collect(),
+        }
+    }
+
+    /// This pattern applied `n` times in a row.
+    ///
+    /// Random inputs and address sequences draw fresh values in every
+    /// repetition, since the whole pattern shares one random stream.
+    pub fn repeat(&self, n: usize) -> Self {
+        Self::concat(std::iter::repeat_n(self.clone(), n))
+    }
+
+    /// Inverts all write data in the pattern (see [`SramInput::inverted`]).
+    ///
+    /// Expected read values follow automatically, so March C- on a zero
+    /// background becomes March C- on a ones background.
+    pub fn inverted(self) -> Self {
+        self.map_data(SramInput::inverted)
+    }
+
+    /// Reverses the address order of every element (see [`AddrSeq::reversed`]).
+    pub fn reversed(mut self) -> Self {
+        for elt in self.elements.iter_mut() {
+            elt.addr_seq = elt.addr_seq.reversed();
+        }
+        self
+    }
+
+    /// Replaces the data of every write in the pattern with `f(data)`.
+    pub fn map_data(mut self, mut f: impl FnMut(SramInput) -> SramInput) -> Self {
+        for op in self.elements.iter_mut().flat_map(|elt| elt.ops.iter_mut()) {
+            op.map_data(&mut f);
+        }
+        self
+    }
+
+    /// Replaces the mask of every write in the pattern with `f(mask)`.
+    pub fn map_masks(mut self, mut f: impl FnMut(SramInput) -> SramInput) -> Self {
+        for op in self.elements.iter_mut().flat_map(|elt| elt.ops.iter_mut()) {
+            op.map_mask(&mut f);
+        }
+        self
+    }
+
+    /// Restricts every read in the pattern to compare only the bits set in
+    /// `compare`, e.g. to ignore don't-care byte lanes.
+    pub fn with_compare(mut self, compare: SramWord) -> Self {
+        for elt in self.elements.iter_mut() {
+            for op in elt.ops.iter_mut() {
+                match op {
+                    SramOp::Read => *op = SramOp::ReadMasked { compare },
+                    SramOp::ReadMasked { compare: existing } => *existing &= compare,
+                    _ => {}
+                }
+            }
+        }
+        self
+    }
+}
+
+impl AddrSeq {
+    /// This sequence with its address order reversed.
+    ///
+    /// Random sequences have no order to reverse and are returned unchanged.
+    pub fn reversed(self) -> Self {
+        match self {
+            AddrSeq::Up => AddrSeq::Down,
+            AddrSeq::Down => AddrSeq::Up,
+            AddrSeq::Rand(n) => AddrSeq::Rand(n),
+            AddrSeq::RandDist { .. } => self,
+            AddrSeq::Complement { descending } => AddrSeq::Complement {
+                descending: !descending,
+            },
+            AddrSeq::Gray { descending } => AddrSeq::Gray {
+                descending: !descending,
+            },
+            AddrSeq::Stride {
+                log2_stride,
+                descending,
+            } => AddrSeq::Stride {
+                log2_stride,
+                descending: !descending,
+            },
+            AddrSeq::RowFast { descending } => AddrSeq::RowFast {
+                descending: !descending,
+            },
+            AddrSeq::ColFast { descending } => AddrSeq::ColFast {
+                descending: !descending,
+            },
+        }
+    }
+
+    /// The addresses visited by this sequence, in order.
+    ///
+    /// Addresses outside `scope` are skipped; random addresses are drawn
+    /// uniformly from within `scope`.
+    pub(crate) fn addrs(
+        &self,
+        size: SramSize,
+        topology: Option<&SramTopology>,
+        scope: &AddrScope,
+        rng: &mut impl RngCore,
+    ) -> Vec<SramAddr> {
+        let depth = size.depth;
+        let (mut addrs, descending): (Vec<SramAddr>, bool) = match *self {
+            AddrSeq::Up => ((0..depth).collect(), false),
+            AddrSeq::Down => ((0..depth).collect(), true),
+            AddrSeq::Rand(n) => {
+                let choices = scope.addrs(depth);
+                if choices.is_empty() {
+                    return Vec::new();
+                }
+                return (0..n)
+                    .map(|_| choices[(rng.next_u64() % choices.len() as u64) as usize])
+                    .collect();
+            }
+            AddrSeq::RandDist { n, dist, cover_all } => {
+                let choices = scope.addrs(depth);
+                return rand_addrs(&choices, n, dist, cover_all, rng);
+            }
+            AddrSeq::Complement { descending } => (
+                (0..depth.div_ceil(2))
+                    .flat_map(|addr| {
+                        let complement = depth - 1 - addr;
+                        if complement == addr {
+                            vec![addr]
+                        } else {
+                            vec![addr, complement]
+                        }
+                    })
+                    .collect(),
+                descending,
+            ),
+            AddrSeq::Gray { descending } => (
+                (0..depth.next_power_of_two())
+                    .map(|i| i ^ (i >> 1))
+                    .filter(|&addr| addr < depth)
+                    .collect(),
+                descending,
+            ),
+            AddrSeq::Stride {
+                log2_stride,
+                descending,
+            } => {
+                let stride = 1u32
+                    .checked_shl(log2_stride)
+                    .expect("address stride too large")
+                    .min(depth);
+                (
+                    (0..stride)
+                        .flat_map(|offset| (offset..depth).step_by(stride as usize))
+                        .collect(),
+                    descending,
+                )
+            }
+            AddrSeq::RowFast { descending } => {
+                let topo = topology.expect("row-fast addressing requires an SRAM topology");
+                (
+                    (0..topo.mux())
+                        .flat_map(|col| (0..topo.rows()).map(move |row| topo.addr(row, col)))
+                        .collect(),
+                    descending,
+                )
+            }
+            AddrSeq::ColFast { descending } => {
+                let topo = topology.expect("column-fast addressing requires an SRAM topology");
+                (
+                    (0..topo.rows())
+                        .flat_map(|row| (0..topo.mux()).map(move |col| topo.addr(row, col)))
+                        .collect(),
+                    descending,
+                )
+            }
+        };
+        if descending {
+            addrs.reverse();
+        }
+        if *scope != AddrScope::Full {
+            addrs.retain(|&addr| scope.contains(addr));
+        }
+        addrs
+    }
 }
 
 impl SramSize {
@@ -197,18 +1228,76 @@ impl SramSize {
     pub fn mask_width(&self) -> SramWord {
         self.mask_width
     }
+
+    /// The standard set of `ceil(log2(width)) + 1` word-oriented data backgrounds.
+    ///
+    /// The first background is all zeros; the `i`-th subsequent background
+    /// alternates runs of `2^(i-1)` ones and zeros (`0x5555...`, `0x3333...`,
+    /// `0x0f0f...`, and so on), masked to the word width.
+    pub fn word_backgrounds(&self) -> Vec<SramWord> {
+        let dmask = u64::MAX >> (64 - self.width);
+        let mut bgs = vec![0];
+        let mut run = 1;
+        while run < self.width {
+            let bg = (0..self.width)
+                .filter(|bit| (bit / run) % 2 == 0)
+                .fold(0, |bg, bit| bg | (1 << bit));
+            bgs.push(bg & dmask);
+            run *= 2;
+        }
+        bgs
+    }
 }
 
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 pub enum FixedSramOp {
+    /// Read `addr`, expecting the bits of `data` selected by `compare`.
     Read {
         addr: SramAddr,
         data: SramWord,
+        compare: SramWord,
     },
     Write {
         addr: SramAddr,
         data: SramWord,
         mask: SramWord,
     },
+    Pause {
+        duration: Duration,
+    },
+    /// Read `addr` and record the value without comparing it.
+    Capture {
+        addr: SramAddr,
+    },
+    /// Read `addr` `count` times in a row, expecting the bits of `data`
+    /// selected by `compare` each time.
+    ReadRepeat {
+        addr: SramAddr,
+        data: SramWord,
+        compare: SramWord,
+        count: u32,
+    },
+    /// Write `addr` `count` times in a row.
+    WriteRepeat {
+        addr: SramAddr,
+        data: SramWord,
+        mask: SramWord,
+        count: u32,
+    },
+}
+
+impl FixedSramOp {
+    /// The address accessed by this operation, if any.
+    pub fn addr(&self) -> Option<SramAddr> {
+        match *self {
+            FixedSramOp::Read { addr, .. }
+            | FixedSramOp::Write { addr, .. }
+            | FixedSramOp::Capture { addr }
+            | FixedSramOp::ReadRepeat { addr, .. }
+            | FixedSramOp::WriteRepeat { addr, .. } => Some(addr),
+            FixedSramOp::Pause { .. } => None,
+        }
+    }
 }
 
 /// A pattern adapted to be specific to a particular SRAM size,
@@ -216,6 +1305,7 @@ pub enum FixedSramOp {
 pub struct FixedPattern {
     pattern: Pattern,
     size: SramSize,
+    topology: Option<SramTopology>,
     seed: u64,
 }
 
@@ -224,76 +1314,384 @@ impl FixedPattern {
         Self {
             pattern,
             size,
+            topology: None,
+            seed,
+        }
+    }
+
+    /// Fixes a pattern to an SRAM with a known physical topology,
+    /// allowing the use of [`SramInput::Background`].
+    pub fn with_topology(pattern: Pattern, topology: SramTopology, seed: u64) -> Self {
+        Self {
+            pattern,
+            size: topology.size(),
+            topology: Some(topology),
             seed,
         }
     }
 
+    pub fn size(&self) -> SramSize {
+        self.size
+    }
+
+    /// See [`Pattern::addr_inputs`].
+    pub fn addr_inputs(&self) -> Vec<SramInput> {
+        self.pattern.addr_inputs()
+    }
+
+    /// Expands the pattern into operations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pattern is invalid; see [`FixedPattern::validate`].
     pub fn ops(&self) -> impl Iterator<Item = FixedSramOp> {
+        self.try_ops()
+            .unwrap_or_else(|err| panic!("invalid pattern: {err}"))
+            .into_iter()
+    }
+
+    /// Checks that every element stays within the SRAM, repeats operations
+    /// at least once, and only reads addresses that have already been
+    /// initialized.
+    pub fn validate(&self) -> Result<(), PatternError> {
+        self.try_ops().map(|_| ())
+    }
+
+    fn try_ops(&self) -> Result<Vec<FixedSramOp>, PatternError> {
         let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(self.seed);
         let mut state = SramState::new(self.size);
         let mut ops = Vec::new();
         let dmask = u64::MAX >> (64 - self.size.width);
-        let mask_mask = u64::MAX >> (64 - self.size.mask_width);
-        for elt in self.pattern.elements.iter() {
-            let addrs: Vec<u32> = match elt.addr_seq {
-                AddrSeq::Up => (0..self.size.depth).collect(),
-                AddrSeq::Down => (0..self.size.depth).rev().collect(),
-                AddrSeq::Rand(n) => (0..n)
-                    .map(|_| (rng.next_u64() % self.size.depth as u64) as u32)
-                    .collect(),
-            };
+        for (element, elt) in self.pattern.elements.iter().enumerate() {
+            if let Some(addr) = elt.scope.out_of_bounds(self.size.depth) {
+                return Err(PatternError::OutOfBounds { element, addr });
+            }
+            if elt.ops.iter().any(|op| {
+                matches!(
+                    op,
+                    SramOp::Repeat { count: 0, .. } | SramOp::Hammer { count: 0, .. }
+                )
+            }) {
+                return Err(PatternError::ZeroRepeat { element });
+            }
+            if elt.is_pause() {
+                for op in elt.ops.iter() {
+                    if let SramOp::Pause(duration) = *op {
+                        ops.push(FixedSramOp::Pause { duration });
+                    }
+                }
+                continue;
+            }
+            let addrs = elt
+                .addr_seq
+                .addrs(self.size, self.topology.as_ref(), &elt.scope, &mut rng);
 
             for addr in addrs {
                 for op in elt.ops.iter() {
-                    let op = match op {
-                        SramOp::Read => FixedSramOp::Read {
-                            addr,
-                            data: state
-                                .read(addr)
-                                .expect("pattern attempted to read from an uninitialized address"),
-                        },
-                        SramOp::Write { data, mask } => FixedSramOp::Write {
+                    let fixed = match op {
+                        SramOp::Read => vec![self.fix_repeat(
+                            RepeatOp::Read,
                             addr,
-                            data: match data {
-                                SramInput::Fixed(data) => *data & dmask,
-                                SramInput::Rand => rng.next_u64() & dmask,
+                            1,
+                            &state,
+                            &mut rng,
+                            element,
+                        )?],
+                        SramOp::Write { data, mask } => vec![self.fix_repeat(
+                            RepeatOp::Write {
+                                data: *data,
+                                mask: *mask,
                             },
-                            mask: match mask {
-                                SramInput::Fixed(mask) => *mask & mask_mask,
-                                SramInput::Rand => rng.next_u64() & mask_mask,
-                            },
-                        },
+                            addr,
+                            1,
+                            &state,
+                            &mut rng,
+                            element,
+                        )?],
+                        SramOp::Repeat { op, count } => {
+                            vec![self.fix_repeat(*op, addr, *count, &state, &mut rng, element)?]
+                        }
+                        SramOp::Hammer { op, count } => {
+                            let topo = self
+                                .topology
+                                .as_ref()
+                                .expect("hammer operations require an SRAM topology");
+                            topo.neighbour_addrs(addr)
+                                .into_iter()
+                                .map(|neighbour| {
+                                    self.fix_repeat(
+                                        *op, neighbour, *count, &state, &mut rng, element,
+                                    )
+                                })
+                                .collect::<Result<Vec<_>, _>>()?
+                        }
+                        SramOp::ReadMasked { compare } => {
+                            let (data, valid) = expect(&state, addr, element)?;
+                            vec![FixedSramOp::Read {
+                                addr,
+                                data,
+                                compare: compare & valid,
+                            }]
+                        }
+                        SramOp::Capture => vec![FixedSramOp::Capture { addr }],
+                        SramOp::Pause(duration) => vec![FixedSramOp::Pause {
+                            duration: *duration,
+                        }],
                         SramOp::Rand { mask } => {
                             if rng.next_u32() & 1 > 0 {
-                                FixedSramOp::Read {
+                                let (data, valid) = expect(&state, addr, element)?;
+                                vec![FixedSramOp::Read {
                                     addr,
-                                    data: state.read(addr).expect(
-                                        "pattern attempted to read from an uninitialized address",
-                                    ),
-                                }
+                                    data,
+                                    compare: valid,
+                                }]
                             } else {
-                                let mask = match mask {
-                                    RandMask::Fixed(mask) => *mask & mask_mask,
-                                    RandMask::Rand => rng.next_u64() & mask_mask,
-                                };
-
-                                FixedSramOp::Write {
+                                let mask = self.rand_mask(mask, &mut rng);
+                                vec![FixedSramOp::Write {
                                     addr,
                                     data: rng.next_u64() & dmask,
                                     mask,
-                                }
+                                }]
+                            }
+                        }
+                        SramOp::RandAccess {
+                            reads,
+                            writes,
+                            mask,
+                            data,
+                        } => {
+                            assert!(
+                                reads + writes > 0,
+                                "reads and writes cannot both have zero weight"
+                            );
+                            if rng.next_u32() % (reads + writes) >= *writes {
+                                let (data, valid) = expect(&state, addr, element)?;
+                                vec![FixedSramOp::Read {
+                                    addr,
+                                    data,
+                                    compare: valid,
+                                }]
+                            } else {
+                                let mask = self.rand_mask(mask, &mut rng);
+                                let data = self.rand_data(data, addr, &mut rng);
+                                vec![FixedSramOp::Write { addr, data, mask }]
                             }
                         }
                     };
 
-                    if let FixedSramOp::Write { data, addr, mask } = op {
-                        state.write(addr, data, mask);
+                    for op in fixed {
+                        if let FixedSramOp::Write { data, addr, mask }
+                        | FixedSramOp::WriteRepeat {
+                            data, addr, mask, ..
+                        } = op
+                        {
+                            state.write(addr, data, mask);
+                        }
+                        ops.push(op);
                     }
-                    ops.push(op);
                 }
             }
         }
 
-        ops.into_iter()
+        Ok(ops)
+    }
+
+    /// Fixes a read or write repeated `count` times at `addr`.
+    fn fix_repeat(
+        &self,
+        op: RepeatOp,
+        addr: SramAddr,
+        count: u32,
+        state: &SramState,
+        rng: &mut impl RngCore,
+        element: usize,
+    ) -> Result<FixedSramOp, PatternError> {
+        Ok(match op {
+            RepeatOp::Read => {
+                let (data, compare) = expect(state, addr, element)?;
+                if count == 1 {
+                    FixedSramOp::Read {
+                        addr,
+                        data,
+                        compare,
+                    }
+                } else {
+                    FixedSramOp::ReadRepeat {
+                        addr,
+                        data,
+                        compare,
+                        count,
+                    }
+                }
+            }
+            RepeatOp::Write { data, mask } => {
+                let data = self.data_input(&data, addr, rng);
+                let mask = self.mask_input(&mask, addr, rng);
+                if count == 1 {
+                    FixedSramOp::Write { addr, data, mask }
+                } else {
+                    FixedSramOp::WriteRepeat {
+                        addr,
+                        data,
+                        mask,
+                        count,
+                    }
+                }
+            }
+        })
+    }
+
+    fn rand_mask(&self, mask: &RandMask, rng: &mut impl RngCore) -> SramWord {
+        let mask_mask = u64::MAX >> (64 - self.size.mask_width);
+        match mask {
+            RandMask::Fixed(mask) => *mask & mask_mask,
+            RandMask::Rand => rng.next_u64() & mask_mask,
+            RandMask::SingleLane => 1 << (rng.next_u64() % self.size.mask_width),
+        }
+    }
+
+    fn rand_data(&self, data: &DataDist, addr: SramAddr, rng: &mut impl RngCore) -> SramWord {
+        let dmask = u64::MAX >> (64 - self.size.width);
+        let rand_bits = |rng: &mut dyn RngCore, bits: u32| {
+            (0..bits).fold(0, |word, _| word | 1 << (rng.next_u64() % self.size.width))
+        };
+        match data {
+            DataDist::Uniform => rng.next_u64() & dmask,
+            DataDist::Sparse { bits } => rand_bits(rng, *bits),
+            DataDist::Relative { base, flips } => {
+                let base = self.data_input(base, addr, rng);
+                base ^ rand_bits(rng, *flips)
+            }
+        }
+    }
+
+    fn data_input(&self, data: &SramInput, addr: SramAddr, rng: &mut impl RngCore) -> SramWord {
+        let dmask = u64::MAX >> (64 - self.size.width);
+        match data {
+            SramInput::Fixed(data) => *data & dmask,
+            SramInput::Rand => rng.next_u64() & dmask,
+            SramInput::Addr | SramInput::InvAddr | SramInput::AddrHash(_) => {
+                data.addr_value(addr).unwrap() & dmask
+            }
+            SramInput::Background { kind, inverted } => {
+                let word = self
+                    .topology
+                    .as_ref()
+                    .expect("background inputs require an SRAM topology")
+                    .background(*kind, addr);
+                if *inverted {
+                    !word & dmask
+                } else {
+                    word
+                }
+            }
+        }
+    }
+
+    fn mask_input(&self, mask: &SramInput, addr: SramAddr, rng: &mut impl RngCore) -> SramWord {
+        let mask_mask = u64::MAX >> (64 - self.size.mask_width);
+        match mask {
+            SramInput::Fixed(mask) => *mask & mask_mask,
+            SramInput::Rand => rng.next_u64() & mask_mask,
+            SramInput::Addr | SramInput::InvAddr | SramInput::AddrHash(_) => {
+                mask.addr_value(addr).unwrap() & mask_mask
+            }
+            SramInput::Background { .. } => {
+                panic!("background inputs cannot be used as write masks")
+            }
+        }
+    }
+}
+
+/// The expected value of a read of `addr` and a mask of its initialized bits.
+///
+/// Only initialized mask lanes are compared, so a read is legal as soon as
+/// any lane of the address has been written.
+fn expect(
+    state: &SramState,
+    addr: SramAddr,
+    element: usize,
+) -> Result<(SramWord, SramWord), PatternError> {
+    state
+        .read_partial(addr)
+        .ok_or(PatternError::UninitializedRead { element, addr })
+}
+
+/// Draws `n` addresses from `choices` according to `dist`, followed by every
+/// choice that was not drawn if `cover_all` is set.
+fn rand_addrs(
+    choices: &[SramAddr],
+    n: u64,
+    dist: AddrDist,
+    cover_all: bool,
+    rng: &mut impl RngCore,
+) -> Vec<SramAddr> {
+    if choices.is_empty() {
+        return Vec::new();
+    }
+    let uniform = |rng: &mut dyn RngCore, range: &[SramAddr]| {
+        range[(rng.next_u64() % range.len() as u64) as usize]
+    };
+    let mut addrs: Vec<SramAddr> = match dist {
+        AddrDist::Uniform => (0..n).map(|_| uniform(rng, choices)).collect(),
+        AddrDist::HotSpot {
+            start,
+            end,
+            percent,
+        } => {
+            assert!(percent <= 100, "hot spot percentage must be at most 100");
+            let hot: Vec<SramAddr> = choices
+                .iter()
+                .copied()
+                .filter(|addr| (start..end).contains(addr))
+                .collect();
+            (0..n)
+                .map(|_| {
+                    if !hot.is_empty() && rng.next_u32() % 100 < percent {
+                        uniform(rng, &hot)
+                    } else {
+                        uniform(rng, choices)
+                    }
+                })
+                .collect()
+        }
+        AddrDist::Locality { max_step } => {
+            let len = choices.len() as i64;
+            let span = 2 * max_step as u64 + 1;
+            let mut idx = (rng.next_u64() % len as u64) as i64;
+            (0..n)
+                .map(|i| {
+                    if i > 0 {
+                        let step = (rng.next_u64() % span) as i64 - max_step as i64;
+                        idx = (idx + step).rem_euclid(len);
+                    }
+                    choices[idx as usize]
+                })
+                .collect()
+        }
+        AddrDist::Neighbourhood { addr, radius } => {
+            let near: Vec<SramAddr> = choices
+                .iter()
+                .copied()
+                .filter(|&other| other.abs_diff(addr) <= radius)
+                .collect();
+            assert!(
+                !near.is_empty(),
+                "neighbourhood contains no addresses in scope"
+            );
+            (0..n).map(|_| uniform(rng, &near)).collect()
+        }
+    };
+    if cover_all {
+        let mut visited = vec![false; choices.iter().max().map_or(0, |&max| max as usize + 1)];
+        for &addr in addrs.iter() {
+            visited[addr as usize] = true;
+        }
+        for &addr in choices {
+            if !std::mem::replace(&mut visited[addr as usize], true) {
+                addrs.push(addr);
+            }
+        }
     }
+    addrs
 }
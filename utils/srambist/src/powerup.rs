@@ -0,0 +1,254 @@
+use crate::executor::Capture;
+use crate::fault::Cell;
+use crate::pattern::{SramAddr, SramSize, SramWord};
+
+/// The contents of an SRAM captured immediately after power-up.
+///
+/// Usually obtained by running [`Pattern::power_up`](crate::pattern::Pattern::power_up)
+/// with [`execute_capturing`](crate::executor::execute_capturing).
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct PowerUpSnapshot {
+    size: SramSize,
+    words: Vec<SramWord>,
+}
+
+impl PowerUpSnapshot {
+    pub fn new(size: SramSize, words: Vec<SramWord>) -> Self {
+        assert_eq!(
+            words.len(),
+            size.depth() as usize,
+            "snapshot must contain one word per address"
+        );
+        let dmask = u64::MAX >> (64 - size.width());
+        assert!(
+            words.iter().all(|&word| word & !dmask == 0),
+            "word wider than SRAM"
+        );
+        Self { size, words }
+    }
+
+    /// Assembles a snapshot from capture reads, which must cover every address.
+    ///
+    /// If an address was captured more than once, the first capture is used.
+    pub fn from_captures(size: SramSize, captures: &[Capture]) -> Self {
+        let mut words = vec![None; size.depth() as usize];
+        for capture in captures {
+            let word = &mut words[capture.addr as usize];
+            if word.is_none() {
+                *word = Some(capture.data);
+            }
+        }
+        let words = words
+            .into_iter()
+            .enumerate()
+            .map(|(addr, word)| word.unwrap_or_else(|| panic!("address {addr:#x} not captured")))
+            .collect();
+        Self::new(size, words)
+    }
+
+    pub fn size(&self) -> SramSize {
+        self.size
+    }
+    pub fn words(&self) -> &[SramWord] {
+        &self.words
+    }
+
+    pub fn word(&self, addr: SramAddr) -> SramWord {
+        assert!(addr < self.size.depth(), "addr out of bounds");
+        self.words[addr as usize]
+    }
+
+    pub fn bit(&self, cell: Cell) -> bool {
+        assert!(
+            (cell.bit as SramWord) < self.size.width(),
+            "bit out of bounds"
+        );
+        self.word(cell.addr) & (1 << cell.bit) != 0
+    }
+
+    /// The number of bits that powered up as 1.
+    pub fn ones(&self) -> u64 {
+        self.words.iter().map(|word| word.count_ones() as u64).sum()
+    }
+
+    /// The number of bits that differ from `other`.
+    pub fn distance(&self, other: &PowerUpSnapshot) -> u64 {
+        assert_eq!(self.size, other.size, "snapshots of different SRAM sizes");
+        self.words
+            .iter()
+            .zip(other.words.iter())
+            .map(|(a, b)| (a ^ b).count_ones() as u64)
+            .sum()
+    }
+}
+
+/// Per-bit statistics over repeated power-up captures of one SRAM.
+#[derive(Clone, Debug)]
+pub struct PowerUpStats {
+    size: SramSize,
+    snapshots: u32,
+    /// The number of captures in which each bit was 1, indexed by `addr * width + bit`.
+    ones: Vec<u32>,
+}
+
+impl PowerUpStats {
+    pub fn new(size: SramSize) -> Self {
+        let bits = size.depth() as usize * size.width() as usize;
+        Self {
+            size,
+            snapshots: 0,
+            ones: vec![0; bits],
+        }
+    }
+
+    pub fn from_snapshots<'a>(
+        size: SramSize,
+        snapshots: impl IntoIterator<Item = &'a PowerUpSnapshot>,
+    ) -> Self {
+        let mut stats = Self::new(size);
+        for snapshot in snapshots {
+            stats.add(snapshot);
+        }
+        stats
+    }
+
+    pub fn add(&mut self, snapshot: &PowerUpSnapshot) {
+        assert_eq!(self.size, snapshot.size, "snapshot of different SRAM size");
+        let width = self.size.width() as usize;
+        for (addr, word) in snapshot.words.iter().enumerate() {
+            for bit in 0..width {
+                if word & (1 << bit) != 0 {
+                    self.ones[addr * width + bit] += 1;
+                }
+            }
+        }
+        self.snapshots += 1;
+    }
+
+    pub fn size(&self) -> SramSize {
+        self.size
+    }
+    pub fn snapshots(&self) -> u32 {
+        self.snapshots
+    }
+
+    fn ones(&self, cell: Cell) -> u32 {
+        assert!(cell_in_bounds(self.size, cell), "cell out of bounds");
+        self.ones[cell.addr as usize * self.size.width() as usize + cell.bit as usize]
+    }
+
+    fn cells(&self) -> impl Iterator<Item = Cell> + '_ {
+        let width = self.size.width() as u32;
+        (0..self.size.depth()).flat_map(move |addr| (0..width).map(move |bit| Cell { addr, bit }))
+    }
+
+    /// The fraction of captures in which `cell` powered up as 1.
+    pub fn bias(&self, cell: Cell) -> f64 {
+        assert!(self.snapshots > 0, "no snapshots recorded");
+        self.ones(cell) as f64 / self.snapshots as f64
+    }
+
+    /// Whether `cell` powered up with the same value in every capture.
+    pub fn is_stable(&self, cell: Cell) -> bool {
+        let ones = self.ones(cell);
+        ones == 0 || ones == self.snapshots
+    }
+
+    /// The fraction of cells that powered up with the same value in every capture.
+    pub fn stability(&self) -> f64 {
+        assert!(self.snapshots > 0, "no snapshots recorded");
+        let stable = self.cells().filter(|&cell| self.is_stable(cell)).count();
+        stable as f64 / self.ones.len() as f64
+    }
+
+    /// The fraction of all captured bits that were 1.
+    pub fn mean_bias(&self) -> f64 {
+        assert!(self.snapshots > 0, "no snapshots recorded");
+        let ones: u64 = self.ones.iter().map(|&ones| ones as u64).sum();
+        ones as f64 / (self.ones.len() as f64 * self.snapshots as f64)
+    }
+
+    /// The cells that did not power up with the same value in every capture.
+    pub fn unstable_cells(&self) -> Vec<Cell> {
+        self.cells().filter(|&cell| !self.is_stable(cell)).collect()
+    }
+
+    /// The stable power-up value of every cell, identifying this SRAM instance.
+    pub fn fingerprint(&self) -> Fingerprint {
+        assert!(self.snapshots > 0, "no snapshots recorded");
+        let mut value = vec![0; self.size.depth() as usize];
+        let mut stable = vec![0; self.size.depth() as usize];
+        for cell in self.cells() {
+            let bit = 1 << cell.bit;
+            if self.ones(cell) == self.snapshots {
+                value[cell.addr as usize] |= bit;
+            }
+            if self.is_stable(cell) {
+                stable[cell.addr as usize] |= bit;
+            }
+        }
+        Fingerprint {
+            size: self.size,
+            value,
+            stable,
+        }
+    }
+}
+
+fn cell_in_bounds(size: SramSize, cell: Cell) -> bool {
+    cell.addr < size.depth() && (cell.bit as SramWord) < size.width()
+}
+
+/// The power-up values of the stable cells of an SRAM.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Fingerprint {
+    size: SramSize,
+    value: Vec<SramWord>,
+    /// A mask of the cells that were stable across every capture.
+    stable: Vec<SramWord>,
+}
+
+impl Fingerprint {
+    pub fn size(&self) -> SramSize {
+        self.size
+    }
+
+    /// The number of cells that were stable across every capture.
+    pub fn stable_bits(&self) -> u64 {
+        self.stable
+            .iter()
+            .map(|word| word.count_ones() as u64)
+            .sum()
+    }
+
+    /// The fraction of cells stable in both fingerprints whose values differ.
+    ///
+    /// Fingerprints of the same SRAM instance are close to 0, while those of
+    /// different instances are close to 0.5. Returns `None` if no cell is
+    /// stable in both.
+    pub fn distance(&self, other: &Fingerprint) -> Option<f64> {
+        assert_eq!(
+            self.size, other.size,
+            "fingerprints of different SRAM sizes"
+        );
+        let (mut compared, mut differing) = (0u64, 0u64);
+        for addr in 0..self.value.len() {
+            let both = self.stable[addr] & other.stable[addr];
+            compared += both.count_ones() as u64;
+            differing += ((self.value[addr] ^ other.value[addr]) & both).count_ones() as u64;
+        }
+        (compared > 0).then(|| differing as f64 / compared as f64)
+    }
+
+    /// The fraction of this fingerprint's stable cells that differ from `snapshot`.
+    pub fn distance_to(&self, snapshot: &PowerUpSnapshot) -> Option<f64> {
+        assert_eq!(self.size, snapshot.size, "snapshot of different SRAM size");
+        let compared = self.stable_bits();
+        let differing: u64 = (0..self.value.len())
+            .map(|addr| {
+                ((self.value[addr] ^ snapshot.words[addr]) & self.stable[addr]).count_ones() as u64
+            })
+            .sum();
+        (compared > 0).then(|| differing as f64 / compared as f64)
+    }
+}
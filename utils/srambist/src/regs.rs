@@ -0,0 +1,80 @@
+//! Register maps of the SramBist and StacController peripherals, generated
+//! from the `REG_WIDTH` maps of the Scala RTL.
+//!
+//! Each register occupies `((width - 1) / 64 + 1) * 8` bytes, and registers
+//! wider than 64 bits are packed least significant word first.
+
+use bitvec::prelude::*;
+
+include!(concat!(env!("OUT_DIR"), "/regmap.rs"));
+
+/// A memory-mapped register.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Reg {
+    name: &'static str,
+    base: u64,
+    offset: u64,
+    width: usize,
+}
+
+impl Reg {
+    pub const fn new(name: &'static str, base: u64, offset: u64, width: usize) -> Self {
+        Self {
+            name,
+            base,
+            offset,
+            width,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The address of the register's first word.
+    pub fn addr(&self) -> u64 {
+        self.base + self.offset
+    }
+
+    /// The number of 64-bit words the register occupies.
+    pub fn words(&self) -> usize {
+        (self.width - 1) / 64 + 1
+    }
+
+    /// The addresses of the register's words, least significant first.
+    pub fn word_addrs(&self) -> impl Iterator<Item = u64> {
+        let addr = self.addr();
+        (0..self.words() as u64).map(move |i| addr + i * 8)
+    }
+
+    /// Packs `value` into the register's words.
+    ///
+    /// `value` may be shorter than the register, in which case it is
+    /// zero-extended, or longer, in which case the excess bits are dropped as
+    /// they would be by the hardware.
+    pub fn pack(&self, value: &BitSlice<u64>) -> Vec<u64> {
+        let mut bits = bitvec![u64, Lsb0; 0; self.words() * 64];
+        let len = value.len().min(self.width);
+        bits[..len].copy_from_bitslice(&value[..len]);
+        bits.into_vec()
+    }
+
+    /// Unpacks the register's value from its words.
+    pub fn unpack(&self, words: &[u64]) -> BitVec<u64> {
+        assert_eq!(
+            words.len(),
+            self.words(),
+            "wrong number of words for register {}",
+            self.name
+        );
+        let mut bits = BitVec::from_slice(words);
+        bits.truncate(self.width);
+        bits
+    }
+}
@@ -1,39 +1,60 @@
 use crate::pattern::{SramAddr, SramSize, SramWord};
 
+/// The expected contents of an SRAM, tracking which mask lanes of each word
+/// have been written.
+#[derive(Clone)]
 pub struct SramState {
     size: SramSize,
-    table: Vec<Option<SramWord>>,
+    table: Vec<SramWord>,
+    /// The initialized mask lanes of each word, one bit per lane.
+    valid: Vec<SramWord>,
 }
 
 impl SramState {
     pub fn new(size: SramSize) -> Self {
-        let table = vec![None; size.depth() as usize];
-        Self { size, table }
+        let table = vec![0; size.depth() as usize];
+        let valid = vec![0; size.depth() as usize];
+        Self { size, table, valid }
     }
 
+    /// The word at `addr`, if every lane has been written.
     pub fn read(&self, addr: SramAddr) -> Option<SramWord> {
         assert!(addr < self.size.depth(), "addr out of bounds");
-        self.table[addr as usize]
+        let mask_mask = u64::MAX >> (64 - self.size.mask_width());
+        (self.valid[addr as usize] == mask_mask).then_some(self.table[addr as usize])
+    }
+
+    /// The word at `addr` and a mask of its initialized bits, if any lane
+    /// has been written. Uninitialized bits read as 0.
+    pub fn read_partial(&self, addr: SramAddr) -> Option<(SramWord, SramWord)> {
+        assert!(addr < self.size.depth(), "addr out of bounds");
+        let valid = self.valid_bits(addr);
+        (valid != 0).then_some((self.table[addr as usize], valid))
+    }
+
+    /// A mask of the initialized bits of the word at `addr`.
+    pub fn valid_bits(&self, addr: SramAddr) -> SramWord {
+        assert!(addr < self.size.depth(), "addr out of bounds");
+        self.lane_bits(self.valid[addr as usize])
     }
 
     pub fn write(&mut self, addr: SramAddr, data: SramWord, mask: SramWord) {
         assert!(addr < self.size.depth(), "addr out of bounds");
 
         let mask_mask = u64::MAX >> (64 - self.size.mask_width());
-        if mask == mask_mask {
-            self.table[addr as usize] = Some(data);
-        } else {
-            let entry = self.table[addr as usize]
-                .as_mut()
-                .expect("cannot perform a partial write on an uninitialized address");
-            let mask_gran = self.size.width() / self.size.mask_width();
-            for i in 0..self.size.mask_width() {
-                if mask & (1 << i) > 0 {
-                    let entry_mask = (u64::MAX >> (64 - mask_gran)) << (i * mask_gran);
-                    *entry &= !entry_mask;
-                    *entry |= entry_mask & data;
-                }
-            }
-        }
+        let bits = self.lane_bits(mask & mask_mask);
+        let entry = &mut self.table[addr as usize];
+        *entry = (*entry & !bits) | (data & bits);
+        self.valid[addr as usize] |= mask & mask_mask;
+    }
+
+    /// Expands a mask with one bit per lane into a mask with one bit per data bit.
+    fn lane_bits(&self, lanes: SramWord) -> SramWord {
+        let mask_gran = self.size.width() / self.size.mask_width();
+        (0..self.size.mask_width())
+            .filter(|i| lanes & (1 << i) > 0)
+            .fold(0, |bits, i| {
+                bits | (u64::MAX >> (64 - mask_gran)) << (i * mask_gran)
+            })
     }
 }
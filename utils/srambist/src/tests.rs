@@ -1,7 +1,30 @@
-use crate::bebe::BebeScratchpadExecutor;
-use crate::executor::{execute, IdealExecutor};
-use crate::pattern::{FixedPattern, Pattern, SramSize};
+use crate::bebe::{Bebe, BebeScratchpadExecutor};
+use crate::coverage::{analyze, FaultUniverse};
+use crate::executor::{execute, execute_capturing, Executor, IdealExecutor};
+use crate::fault::{Cell, DecoderFault, Fault, FaultClass, FaultyExecutor};
+use crate::generate::generate;
+use crate::mmio::{MmioExecutor, SramBist, Transport};
+use crate::pattern::{
+    AddrDist, AddrScope, AddrSeq, Background, DataDist, Element, FixedPattern, FixedSramOp,
+    Pattern, PatternError, RandConfig, RandMask, RepeatOp, SramAddr, SramInput, SramOp, SramSize,
+    SramWord,
+};
+use crate::powerup::{PowerUpSnapshot, PowerUpStats};
+use crate::regs::{sram_bist, Reg};
 use crate::testsite::sweep_tdc_test;
+use crate::topology::{BitLayout, ColumnBits, PhysicalCell, SramTopology, STAC_NUM_SRAMS};
+#[cfg(feature = "unverified-bist")]
+use crate::{
+    bebe::{BebeBistExecutor, BebeExecutor},
+    bist::{
+        compile, decompile, BistCompileError, BistOutcome, BistProgram, BistRegisters,
+        BistRunConfig, ELEMENT_SEQUENCE_WORDS, PATTERN_TABLE_WORDS,
+    },
+    crosscheck::{CrossCheck, RandomElementError},
+    golden::{simulate, Lfsr, Misr},
+    mmio::{BistExecutor, BistRunError},
+};
+use std::time::Duration;
 
 /// The size of the scratchpad on the STAC-V1 test chip.
 const STAC_SCRATCHPAD_SIZE: SramSize = SramSize {
@@ -34,6 +57,1156 @@ fn rand4096_ideal_executor() {
     execute(pat, ex).expect("Rand 4096 pattern should execute correctly with an ideal executor");
 }
 
+#[test]
+fn march_cm_fault_free_executor() {
+    let size = SramSize::new(32, 256, 4);
+    let ex = FaultyExecutor::new(size, vec![]);
+    let pat = FixedPattern::new(Pattern::march_cm(), size, 1);
+    execute(pat, ex).expect("March C- pattern should pass on a fault-free SRAM");
+}
+
+#[test]
+fn march_cm_detects_stuck_at() {
+    let size = SramSize::new(32, 256, 4);
+    let fault = Fault::StuckAt {
+        cell: Cell { addr: 17, bit: 5 },
+        value: true,
+    };
+    let ex = FaultyExecutor::new(size, vec![fault]);
+    let pat = FixedPattern::new(Pattern::march_cm(), size, 1);
+    execute(pat, ex).expect_err("March C- pattern should detect a stuck-at-1 fault");
+}
+
+#[test]
+fn mats_plus_vs_march_cm_coverage() {
+    let size = SramSize::new(2, 8, 1);
+    let universe = FaultUniverse::exhaustive(size);
+    let mats_plus = analyze(&FixedPattern::new(Pattern::mats_plus(), size, 1), &universe);
+    let march_cm = analyze(&FixedPattern::new(Pattern::march_cm(), size, 1), &universe);
+    println!("MATS+:\n{mats_plus}");
+    println!("March C-:\n{march_cm}");
+
+    for class in [FaultClass::StuckAt, FaultClass::AddressDecoder] {
+        assert_eq!(mats_plus.class(class).ratio(), Some(1.0));
+    }
+    for class in [
+        FaultClass::StuckAt,
+        FaultClass::Transition,
+        FaultClass::AddressDecoder,
+    ] {
+        assert_eq!(march_cm.class(class).ratio(), Some(1.0));
+    }
+    for class in FaultClass::ALL {
+        assert!(march_cm.class(class).detected >= mats_plus.class(class).detected);
+    }
+    assert_eq!(
+        march_cm
+            .class(FaultClass::DeceptiveReadDestructive)
+            .detected,
+        0
+    );
+}
+
+#[test]
+fn generate_saf_tf_test() {
+    let size = SramSize::new(2, 8, 1);
+    let classes = [FaultClass::StuckAt, FaultClass::Transition];
+    let test = generate(&classes, size);
+    println!("{:?}", test.pattern);
+    assert!(test.undetected.is_empty());
+    assert!(test.ops_per_addr() <= 6);
+
+    // The generated test should carry over to larger SRAMs.
+    let size = SramSize::new(32, 64, 4);
+    let universe = FaultUniverse::sampled(size, 64, 1);
+    let report = analyze(&FixedPattern::new(test.pattern, size, 1), &universe);
+    for class in classes {
+        assert_eq!(report.class(class).ratio(), Some(1.0));
+    }
+}
+
+#[test]
+fn generate_coupling_test() {
+    let size = SramSize::new(1, 8, 1);
+    let classes = [
+        FaultClass::AddressDecoder,
+        FaultClass::IdempotentCoupling,
+        FaultClass::InversionCoupling,
+    ];
+    let test = generate(&classes, size);
+    println!("{:?}", test.pattern);
+    assert!(test.undetected.is_empty());
+    assert!(test.ops_per_addr() <= 10);
+}
+
+#[test]
+fn stac_topology_round_trip() {
+    for id in 0..STAC_NUM_SRAMS {
+        for topo in [
+            SramTopology::stac(id),
+            SramTopology::stac(id)
+                .with_column_bits(ColumnBits::High)
+                .with_bit_layout(BitLayout::Contiguous),
+        ] {
+            let size = topo.size();
+            assert_eq!(
+                topo.rows() as u64 * topo.cols() as u64,
+                size.depth() as u64 * size.width()
+            );
+            for addr in 0..size.depth() {
+                assert_eq!(topo.addr(topo.row(addr), topo.col_sel(addr)), addr);
+                for bit in 0..size.width() as u32 {
+                    let cell = Cell { addr, bit };
+                    assert_eq!(topo.logical(topo.physical(cell)), cell);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn stac_topology_neighbours() {
+    let topo = SramTopology::stac(0);
+    assert_eq!((topo.rows(), topo.cols()), (256, 256));
+
+    // Bit 0 of address 9 is in row 1, column 1 of the interleaved array.
+    let cell = Cell { addr: 9, bit: 0 };
+    assert_eq!(topo.physical(cell), PhysicalCell { row: 1, col: 1 });
+    let mut neighbours = topo.neighbours(cell);
+    neighbours.sort();
+    assert_eq!(
+        neighbours,
+        vec![
+            Cell { addr: 1, bit: 0 },
+            Cell { addr: 8, bit: 0 },
+            Cell { addr: 10, bit: 0 },
+            Cell { addr: 17, bit: 0 },
+        ]
+    );
+    assert_eq!(topo.neighbour_addrs(0), vec![1, 7, 8]);
+}
+
+#[test]
+fn stac_topology_backgrounds() {
+    let topo = SramTopology::stac(0);
+    // Interleaved bits share a column parity, so every word is solid.
+    assert_eq!(topo.background(Background::Checkerboard, 0), 0);
+    assert_eq!(topo.background(Background::Checkerboard, 1), 0xffffffff);
+    assert_eq!(topo.background(Background::Checkerboard, 8), 0xffffffff);
+    assert_eq!(topo.background(Background::RowStripe, 7), 0);
+    assert_eq!(topo.background(Background::RowStripe, 8), 0xffffffff);
+    assert_eq!(topo.background(Background::ColStripe, 3), 0xffffffff);
+
+    let topo = topo.with_bit_layout(BitLayout::Contiguous);
+    assert_eq!(topo.background(Background::Checkerboard, 0), 0xaaaaaaaa);
+    assert_eq!(topo.background(Background::Checkerboard, 8), 0x55555555);
+    assert_eq!(topo.background(Background::ColStripe, 8), 0xaaaaaaaa);
+}
+
+#[test]
+fn march_cm_checkerboard_ideal_executor() {
+    let topo = SramTopology::stac(6);
+    let ex = IdealExecutor::new(topo.size());
+    let pat = Pattern::march_cm().with_background(Background::Checkerboard);
+    let pat = FixedPattern::with_topology(pat, topo, 1);
+    execute(pat, ex).expect("March C- checkerboard pattern should execute correctly");
+}
+
+#[test]
+fn word_backgrounds() {
+    assert_eq!(
+        SramSize::new(32, 16, 4).word_backgrounds(),
+        vec![0, 0x55555555, 0x33333333, 0x0f0f0f0f, 0x00ff00ff, 0x0000ffff]
+    );
+    assert_eq!(
+        SramSize::new(24, 16, 1).word_backgrounds(),
+        vec![0, 0x555555, 0x333333, 0x0f0f0f, 0xff00ff, 0x00ffff]
+    );
+    assert_eq!(SramSize::new(1, 16, 1).word_backgrounds(), vec![0]);
+}
+
+#[test]
+fn word_oriented_march_cm_coverage() {
+    let size = SramSize::new(4, 4, 1);
+    let classes = [
+        FaultClass::StateCoupling,
+        FaultClass::IdempotentCoupling,
+        FaultClass::InversionCoupling,
+    ];
+    let universe = FaultUniverse::exhaustive_of(size, &classes);
+    let bit = analyze(&FixedPattern::new(Pattern::march_cm(), size, 1), &universe);
+    let word = analyze(
+        &FixedPattern::new(Pattern::march_cm().word_oriented(size), size, 1),
+        &universe,
+    );
+    println!("Bit-oriented March C-:\n{bit}");
+    println!("Word-oriented March C-:\n{word}");
+    assert!(bit.total().ratio() < Some(1.0));
+    assert_eq!(word.total().ratio(), Some(1.0));
+}
+
+/// Expands a single write element with the given address sequence and returns
+/// the addresses written, checking that every address is visited exactly once.
+fn addr_seq_visits(addr_seq: AddrSeq, topo: SramTopology) -> Vec<SramAddr> {
+    let pattern = Pattern {
+        elements: vec![Element {
+            addr_seq,
+            scope: AddrScope::Full,
+            ops: vec![SramOp::Write {
+                data: SramInput::Fixed(0),
+                mask: SramInput::Fixed(u64::MAX),
+            }],
+        }],
+    };
+    let addrs: Vec<SramAddr> = FixedPattern::with_topology(pattern, topo, 1)
+        .ops()
+        .map(|op| match op {
+            FixedSramOp::Write { addr, .. } => addr,
+            _ => unreachable!(),
+        })
+        .collect();
+    let mut sorted = addrs.clone();
+    sorted.sort_unstable();
+    assert_eq!(
+        sorted,
+        (0..topo.size().depth()).collect::<Vec<_>>(),
+        "{addr_seq:?} should visit every address exactly once"
+    );
+    addrs
+}
+
+fn addr_seq_topologies() -> Vec<SramTopology> {
+    vec![
+        SramTopology::stac(0),
+        SramTopology::stac(3),
+        SramTopology::new(SramSize::new(8, 24, 1), 4),
+    ]
+}
+
+#[test]
+fn addr_seq_complement() {
+    for topo in addr_seq_topologies() {
+        let depth = topo.size().depth();
+        let addrs = addr_seq_visits(AddrSeq::Complement { descending: false }, topo);
+        assert_eq!(&addrs[..4], &[0, depth - 1, 1, depth - 2]);
+        let desc = addr_seq_visits(AddrSeq::Complement { descending: true }, topo);
+        assert_eq!(desc, addrs.into_iter().rev().collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn addr_seq_gray() {
+    for topo in addr_seq_topologies() {
+        let addrs = addr_seq_visits(AddrSeq::Gray { descending: false }, topo);
+        assert_eq!(&addrs[..4], &[0, 1, 3, 2]);
+        let steps: Vec<u32> = addrs
+            .windows(2)
+            .map(|pair| (pair[0] ^ pair[1]).count_ones())
+            .collect();
+        if topo.size().depth().is_power_of_two() {
+            assert!(steps.iter().all(|&bits| bits == 1));
+        } else {
+            // Skipping out-of-range codes breaks the single-bit property.
+            assert!(steps.iter().any(|&bits| bits > 1));
+        }
+        addr_seq_visits(AddrSeq::Gray { descending: true }, topo);
+    }
+}
+
+#[test]
+fn addr_seq_stride() {
+    for topo in addr_seq_topologies() {
+        for log2_stride in 0..12 {
+            for descending in [false, true] {
+                addr_seq_visits(
+                    AddrSeq::Stride {
+                        log2_stride,
+                        descending,
+                    },
+                    topo,
+                );
+            }
+        }
+        let addrs = addr_seq_visits(
+            AddrSeq::Stride {
+                log2_stride: 2,
+                descending: false,
+            },
+            topo,
+        );
+        assert_eq!(&addrs[..3], &[0, 4, 8]);
+    }
+}
+
+#[test]
+fn addr_seq_row_fast() {
+    for topo in addr_seq_topologies() {
+        let addrs = addr_seq_visits(AddrSeq::RowFast { descending: false }, topo);
+        for (i, &addr) in addrs.iter().enumerate() {
+            assert_eq!(topo.row(addr), i as u32 % topo.rows());
+            assert_eq!(topo.col_sel(addr), i as u32 / topo.rows());
+        }
+        addr_seq_visits(AddrSeq::RowFast { descending: true }, topo);
+    }
+}
+
+#[test]
+fn addr_seq_col_fast() {
+    for topo in addr_seq_topologies() {
+        let addrs = addr_seq_visits(AddrSeq::ColFast { descending: false }, topo);
+        assert_eq!(addrs, (0..topo.size().depth()).collect::<Vec<_>>());
+        let topo = topo.with_column_bits(ColumnBits::High);
+        let addrs = addr_seq_visits(AddrSeq::ColFast { descending: true }, topo);
+        assert_eq!(topo.col_sel(addrs[0]), topo.mux() - 1);
+        assert_eq!(topo.col_sel(addrs[1]), topo.mux() - 2);
+    }
+}
+
+#[test]
+fn march_cm_window() {
+    let size = SramSize::new(64, 512, 8);
+    let scope = AddrScope::Range {
+        start: 0x1e0,
+        end: 0x200,
+    };
+    let pat = FixedPattern::new(Pattern::march_cm().within(scope.clone()), size, 1);
+    pat.validate().expect("windowed March C- should be valid");
+    let ops: Vec<FixedSramOp> = pat.ops().collect();
+    assert_eq!(ops.len(), 10 * 32);
+    assert!(ops
+        .iter()
+        .all(|op| op.addr().is_some_and(|addr| scope.contains(addr))));
+    execute(pat, IdealExecutor::new(size)).expect("windowed March C- should pass");
+
+    let scope = AddrScope::List(vec![0x1f1, 0x1ef, 0x1f0]);
+    let pat = FixedPattern::new(Pattern::mats_plus().within(scope), size, 1);
+    let addrs: Vec<SramAddr> = pat
+        .ops()
+        .filter_map(|op| match op {
+            FixedSramOp::Read { addr, .. } => Some(addr),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(addrs, vec![0x1ef, 0x1f0, 0x1f1, 0x1f1, 0x1f0, 0x1ef]);
+}
+
+#[test]
+fn window_validation() {
+    let size = SramSize::new(64, 512, 8);
+    let scope = AddrScope::Range {
+        start: 0x1f0,
+        end: 0x210,
+    };
+    let pat = FixedPattern::new(Pattern::mats_plus().within(scope), size, 1);
+    assert_eq!(
+        pat.validate(),
+        Err(PatternError::OutOfBounds {
+            element: 0,
+            addr: 0x200
+        })
+    );
+
+    // Reading outside the initialized window is an error.
+    let mut pattern = Pattern::mats_plus();
+    pattern.elements[0].scope = AddrScope::Range {
+        start: 0x1e0,
+        end: 0x200,
+    };
+    let pat = FixedPattern::new(pattern, size, 1);
+    assert_eq!(
+        pat.validate(),
+        Err(PatternError::UninitializedRead {
+            element: 1,
+            addr: 0
+        })
+    );
+}
+
+#[test]
+fn address_as_data_inputs() {
+    let size = SramSize::new(24, 64, 1);
+    assert_eq!(SramInput::Addr.owner(0x2a, size), Some(0x2a));
+    assert_eq!(SramInput::InvAddr.owner(0xffffd5, size), Some(0x2a));
+    assert_eq!(SramInput::Addr.owner(0x40, size), None);
+    let hash = SramInput::AddrHash(7);
+    let data = hash.addr_value(0x11).unwrap() & 0xffffff;
+    assert_eq!(hash.owner(data, size), Some(0x11));
+
+    // Narrow words cannot distinguish every address.
+    let size = SramSize::new(4, 64, 1);
+    assert_eq!(SramInput::Addr.owner(0x3, size), None);
+
+    let size = SramSize::new(32, 256, 4);
+    execute(
+        FixedPattern::new(Pattern::address_as_data(), size, 1),
+        IdealExecutor::new(size),
+    )
+    .expect("address-as-data pattern should pass on an ideal SRAM");
+}
+
+#[test]
+fn address_as_data_decodes_aliasing() {
+    let size = SramSize::new(32, 256, 4);
+    let fault = Fault::AddressDecoder(DecoderFault::Alias {
+        addr: 0x1f,
+        other: 0xf1,
+    });
+    let ex = FaultyExecutor::new(size, vec![fault]);
+    let errors = execute(FixedPattern::new(Pattern::address_as_data(), size, 1), ex)
+        .expect_err("address-as-data pattern should detect aliasing");
+    let first = errors.errors[0];
+    assert_eq!(first.addr(), 0x1f);
+    assert_eq!(
+        first.owner(&[SramInput::Addr, SramInput::InvAddr], size),
+        Some(0xf1)
+    );
+
+    // The failure report names the aliased address.
+    let pattern = Pattern::address_as_data();
+    assert_eq!(
+        pattern.addr_inputs(),
+        vec![SramInput::Addr, SramInput::InvAddr]
+    );
+    assert!(first
+        .describe(&pattern.addr_inputs(), size)
+        .ends_with("data belongs to 0xf1"));
+}
+
+#[test]
+fn retention_pattern() {
+    let size = SramSize::new(4, 16, 1);
+    let pattern = Pattern::retention(Duration::from_millis(100));
+    let ops: Vec<FixedSramOp> = FixedPattern::new(pattern.clone(), size, 1).ops().collect();
+    let pauses: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| matches!(op, FixedSramOp::Pause { .. }))
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(pauses, vec![16, 16 * 3 + 1]);
+
+    let universe = FaultUniverse::exhaustive_of(size, &[FaultClass::DataRetention]);
+    let retention = analyze(&FixedPattern::new(pattern, size, 1), &universe);
+    let march_cm = analyze(&FixedPattern::new(Pattern::march_cm(), size, 1), &universe);
+    assert_eq!(
+        retention.class(FaultClass::DataRetention).ratio(),
+        Some(1.0)
+    );
+    assert_eq!(march_cm.class(FaultClass::DataRetention).detected, 0);
+}
+
+#[test]
+fn repeat_ops_expand_to_single_op() {
+    let size = SramSize::new(4, 4, 1);
+    let pattern = Pattern {
+        elements: vec![Element {
+            addr_seq: AddrSeq::Up,
+            scope: AddrScope::Full,
+            ops: vec![
+                SramOp::Repeat {
+                    op: RepeatOp::Write {
+                        data: SramInput::Fixed(0x5),
+                        mask: SramInput::Fixed(u64::MAX),
+                    },
+                    count: 4,
+                },
+                SramOp::Repeat {
+                    op: RepeatOp::Read,
+                    count: 3,
+                },
+            ],
+        }],
+    };
+    let ops: Vec<FixedSramOp> = FixedPattern::new(pattern, size, 1).ops().collect();
+    assert_eq!(ops.len(), 8);
+    assert_eq!(
+        ops[0],
+        FixedSramOp::WriteRepeat {
+            addr: 0,
+            data: 0x5,
+            mask: 0x1,
+            count: 4
+        }
+    );
+    assert_eq!(
+        ops[1],
+        FixedSramOp::ReadRepeat {
+            addr: 0,
+            data: 0x5,
+            compare: 0xf,
+            count: 3
+        }
+    );
+
+    let pattern = Pattern::builder()
+        .element(Element::new(
+            AddrSeq::Up,
+            vec![SramOp::write(SramInput::Fixed(0))],
+        ))
+        .element(Element::new(
+            AddrSeq::Up,
+            vec![SramOp::Repeat {
+                op: RepeatOp::Read,
+                count: 0,
+            }],
+        ))
+        .build();
+    assert_eq!(
+        FixedPattern::new(pattern, size, 1).validate(),
+        Err(PatternError::ZeroRepeat { element: 1 })
+    );
+}
+
+#[test]
+fn hammer_pattern() {
+    let topo = SramTopology::new(SramSize::new(4, 16, 1), 4);
+    let size = topo.size();
+    let pattern = Pattern::hammer(8);
+    execute(
+        FixedPattern::with_topology(pattern.clone(), topo, 1),
+        IdealExecutor::new(size),
+    )
+    .expect("hammer test failed on ideal SRAM");
+
+    let classes = [
+        FaultClass::DeceptiveReadDestructive,
+        FaultClass::WriteDisturb,
+    ];
+    let universe = FaultUniverse::exhaustive_of(size, &classes);
+    let hammer = analyze(&FixedPattern::with_topology(pattern, topo, 1), &universe);
+    let march_cm = analyze(&FixedPattern::new(Pattern::march_cm(), size, 1), &universe);
+    for class in classes {
+        assert_eq!(hammer.class(class).ratio(), Some(1.0));
+        assert!(march_cm.class(class).ratio() < Some(1.0));
+    }
+}
+
+#[test]
+fn power_up_capture() {
+    let size = SramSize::new(4, 16, 1);
+    let pat = FixedPattern::new(Pattern::power_up(), size, 1);
+    pat.validate()
+        .expect("capturing uninitialized addresses should be valid");
+    let fault = Fault::StuckAt {
+        cell: Cell { addr: 5, bit: 2 },
+        value: true,
+    };
+    let captures = execute_capturing(pat, FaultyExecutor::new(size, vec![fault]))
+        .expect("capture reads should never fail");
+    let snapshot = PowerUpSnapshot::from_captures(size, &captures);
+    assert_eq!(snapshot.ones(), 1);
+    assert!(snapshot.bit(Cell { addr: 5, bit: 2 }));
+
+    // An ideal SRAM powers up to all zeros.
+    let pat = FixedPattern::new(Pattern::power_up(), size, 1);
+    let captures =
+        execute_capturing(pat, IdealExecutor::new(size)).expect("capture reads should never fail");
+    let snapshot = PowerUpSnapshot::from_captures(size, &captures);
+    assert_eq!(snapshot.ones(), 0);
+}
+
+#[test]
+fn power_up_stats() {
+    let size = SramSize::new(4, 4, 1);
+    let a = [
+        PowerUpSnapshot::new(size, vec![0b1010, 0b0110, 0b0000, 0b1111]),
+        PowerUpSnapshot::new(size, vec![0b1010, 0b0111, 0b0000, 0b1111]),
+        PowerUpSnapshot::new(size, vec![0b1011, 0b0110, 0b0000, 0b1111]),
+    ];
+    let stats = PowerUpStats::from_snapshots(size, &a);
+    assert_eq!(stats.snapshots(), 3);
+    assert_eq!(
+        stats.unstable_cells(),
+        vec![Cell { addr: 0, bit: 0 }, Cell { addr: 1, bit: 0 }]
+    );
+    assert_eq!(stats.stability(), 14.0 / 16.0);
+    assert_eq!(stats.bias(Cell { addr: 0, bit: 0 }), 1.0 / 3.0);
+    assert_eq!(stats.bias(Cell { addr: 3, bit: 3 }), 1.0);
+
+    let fingerprint = stats.fingerprint();
+    assert_eq!(fingerprint.stable_bits(), 14);
+    assert_eq!(fingerprint.distance(&fingerprint), Some(0.0));
+    assert_eq!(fingerprint.distance_to(&a[0]), Some(0.0));
+
+    let other = PowerUpSnapshot::new(size, vec![0b0101, 0b1001, 0b1111, 0b0000]);
+    let other = PowerUpStats::from_snapshots(size, [&other]).fingerprint();
+    assert_eq!(fingerprint.distance(&other), Some(1.0));
+}
+
+#[test]
+fn compare_mask_ignores_dont_care_bits() {
+    let size = SramSize::new(24, 16, 3);
+    let fault = |bit| {
+        vec![Fault::StuckAt {
+            cell: Cell { addr: 3, bit },
+            value: true,
+        }]
+    };
+    // Only compare the low byte lane.
+    let pattern = Pattern::march_cm().with_compare(0xff);
+    execute(
+        FixedPattern::new(pattern.clone(), size, 1),
+        FaultyExecutor::new(size, fault(12)),
+    )
+    .expect("fault in ignored lane should not be reported");
+
+    let errors = execute(
+        FixedPattern::new(pattern, size, 1),
+        FaultyExecutor::new(size, fault(4)),
+    )
+    .expect_err("fault in compared lane should be reported")
+    .errors;
+    assert!(errors
+        .iter()
+        .all(|err| err.addr() == 3 && err.compare() == 0xff && err.failing_bits() == 1 << 4));
+}
+
+#[test]
+fn partial_writes_to_fresh_addresses() {
+    let size = SramSize::new(16, 4, 2);
+    let pattern = Pattern {
+        elements: vec![Element {
+            addr_seq: AddrSeq::Up,
+            scope: AddrScope::Full,
+            ops: vec![
+                SramOp::Write {
+                    data: SramInput::Fixed(0xabcd),
+                    mask: SramInput::Fixed(0b01),
+                },
+                SramOp::Read,
+                SramOp::Write {
+                    data: SramInput::Fixed(0x1200),
+                    mask: SramInput::Fixed(0b10),
+                },
+                SramOp::Read,
+            ],
+        }],
+    };
+    let pat = FixedPattern::new(pattern.clone(), size, 1);
+    let reads: Vec<FixedSramOp> = pat
+        .ops()
+        .filter(|op| matches!(op, FixedSramOp::Read { .. }))
+        .take(2)
+        .collect();
+    assert_eq!(
+        reads,
+        vec![
+            FixedSramOp::Read {
+                addr: 0,
+                data: 0x00cd,
+                compare: 0x00ff
+            },
+            FixedSramOp::Read {
+                addr: 0,
+                data: 0x12cd,
+                compare: 0xffff
+            },
+        ]
+    );
+    execute(pat, IdealExecutor::new(size)).expect("partial writes should pass");
+
+    // Initialize only the low lane, then apply random masked writes and reads.
+    let rand = Pattern {
+        elements: vec![
+            Element {
+                addr_seq: AddrSeq::Up,
+                scope: AddrScope::Full,
+                ops: vec![SramOp::Write {
+                    data: SramInput::Rand,
+                    mask: SramInput::Fixed(0b01),
+                }],
+            },
+            Element {
+                addr_seq: AddrSeq::Rand(64),
+                scope: AddrScope::Full,
+                ops: vec![SramOp::Rand {
+                    mask: RandMask::Rand,
+                }],
+            },
+        ],
+    };
+    let rand = FixedPattern::new(rand, size, 7);
+    rand.validate()
+        .expect("random masked writes to fresh addresses should be valid");
+    execute(rand, IdealExecutor::new(size)).expect("random pattern should pass");
+}
+
+#[test]
+fn pattern_combinators() {
+    let topo = SramTopology::new(SramSize::new(8, 16, 2), 4);
+    let size = topo.size();
+    let plan = Pattern::march_cm()
+        .then(Pattern::march_cm().with_background(Background::Checkerboard))
+        .then(Pattern::rand(16).repeat(10));
+    assert_eq!(plan.elements().len(), 6 + 6 + 2 * 10);
+    execute(
+        FixedPattern::with_topology(plan, topo, 1),
+        IdealExecutor::new(size),
+    )
+    .expect("composed test plan failed on ideal SRAM");
+
+    let inverted = Pattern::mats_plus().inverted();
+    assert_eq!(
+        inverted.elements()[0].ops(),
+        &[SramOp::write(SramInput::Fixed(!0))]
+    );
+    assert_eq!(inverted.clone().inverted(), Pattern::mats_plus());
+    let reversed = Pattern::mats_plus().reversed();
+    assert_eq!(reversed.elements()[2].addr_seq(), AddrSeq::Up);
+    assert_eq!(reversed.reversed(), Pattern::mats_plus());
+
+    let byte_lanes = Pattern::mats_plus().map_masks(|_| SramInput::Fixed(0b01));
+    let ops: Vec<FixedSramOp> = FixedPattern::new(byte_lanes, size, 1).ops().collect();
+    assert!(ops.iter().all(|op| match *op {
+        FixedSramOp::Write { mask, .. } => mask == 0b01,
+        FixedSramOp::Read { compare, .. } => compare == 0x0f,
+        _ => false,
+    }));
+
+    let built = Pattern::builder()
+        .pattern(Pattern::mats_plus())
+        .pause(Duration::from_millis(1))
+        .element(Element::new(AddrSeq::Up, [SramOp::Read]).with_scope(AddrScope::List(vec![3])))
+        .build();
+    assert_eq!(built.elements().len(), 5);
+    assert!(built.elements()[3].is_pause());
+}
+
+#[test]
+fn rand_config_preset_is_unchanged() {
+    let legacy = Pattern {
+        elements: vec![
+            Element::new(
+                AddrSeq::Up,
+                [SramOp::write(SramInput::Fixed(0)), SramOp::Read],
+            ),
+            Element::new(
+                AddrSeq::Rand(100),
+                [SramOp::Rand {
+                    mask: RandMask::Fixed(u64::MAX),
+                }],
+            ),
+        ],
+    };
+    assert_eq!(Pattern::rand(100), legacy);
+    assert_eq!(Pattern::rand_with(&RandConfig::new(100)), legacy);
+}
+
+#[test]
+fn rand_config_distributions() {
+    let size = SramSize::new(16, 64, 2);
+    let run = |config: RandConfig| -> Vec<FixedSramOp> {
+        let pat = FixedPattern::new(Pattern::rand_with(&config), size, 3);
+        let ops: Vec<FixedSramOp> = pat.ops().skip(2 * size.depth() as usize).collect();
+        execute(pat, IdealExecutor::new(size)).expect("random pattern failed on ideal SRAM");
+        ops
+    };
+
+    let writes = run(RandConfig {
+        reads: 0,
+        mask: RandMask::SingleLane,
+        data: DataDist::Sparse { bits: 2 },
+        ..RandConfig::new(200)
+    });
+    assert!(writes.iter().all(|op| match *op {
+        FixedSramOp::Write { data, mask, .. } => data.count_ones() <= 2 && mask.count_ones() == 1,
+        _ => false,
+    }));
+
+    let hot = run(RandConfig {
+        addrs: AddrDist::HotSpot {
+            start: 8,
+            end: 12,
+            percent: 100,
+        },
+        data: DataDist::Relative {
+            base: SramInput::Fixed(0xff00),
+            flips: 1,
+        },
+        ..RandConfig::new(200)
+    });
+    assert!(hot.iter().all(|op| (8..12).contains(&op.addr().unwrap())));
+    assert!(hot.iter().all(|op| match *op {
+        FixedSramOp::Write { data, .. } => (data ^ 0xff00).count_ones() <= 1,
+        _ => true,
+    }));
+
+    let near = run(RandConfig {
+        addrs: AddrDist::Neighbourhood {
+            addr: 40,
+            radius: 2,
+        },
+        ..RandConfig::new(50)
+    });
+    assert!(near
+        .iter()
+        .all(|op| (38..=42).contains(&op.addr().unwrap())));
+
+    let local = run(RandConfig {
+        addrs: AddrDist::Locality { max_step: 1 },
+        ..RandConfig::new(50)
+    });
+    assert!(local.windows(2).all(|ops| {
+        let step = ops[0].addr().unwrap().abs_diff(ops[1].addr().unwrap());
+        step <= 1 || step == size.depth() - 1
+    }));
+
+    let covered = run(RandConfig {
+        addrs: AddrDist::Neighbourhood { addr: 0, radius: 3 },
+        cover_all: true,
+        ..RandConfig::new(10)
+    });
+    let mut addrs: Vec<SramAddr> = covered.iter().map(|op| op.addr().unwrap()).collect();
+    assert_eq!(addrs.len(), 10 + 60);
+    addrs.sort_unstable();
+    addrs.dedup();
+    assert_eq!(addrs, (0..size.depth()).collect::<Vec<_>>());
+}
+
+#[cfg(feature = "unverified-bist")]
+#[test]
+fn bist_compile_round_trip() {
+    let topo = SramTopology::stac(2);
+    let size = topo.size();
+    for pattern in [
+        Pattern::mats_plus(),
+        Pattern::march_cm(),
+        Pattern::march_cm().inverted(),
+        Pattern::rand(size.depth() as u64),
+    ] {
+        let program = compile(&pattern, &topo).expect("pattern should compile");
+        let regs = program.registers();
+        assert_eq!(BistProgram::decode(&regs).unwrap().registers(), regs);
+        let decompiled = decompile(&regs).unwrap();
+        let ops =
+            |pattern| -> Vec<FixedSramOp> { FixedPattern::new(pattern, size, 1).ops().collect() };
+        assert_eq!(ops(decompiled), ops(pattern));
+    }
+
+    let program = compile(&Pattern::march_cm(), &topo).unwrap();
+    // The full write mask and the zero background; ones are the inverted background.
+    assert_eq!(program.table, vec![0xf, 0]);
+    assert_eq!((program.max_row_addr, program.max_col_addr), (15, 3));
+}
+
+#[cfg(feature = "unverified-bist")]
+#[test]
+fn bist_failure_location() {
+    let topo = SramTopology::stac(2);
+    let size = topo.size();
+    let pattern = Pattern::march_cm().then(Pattern::rand(size.depth() as u64));
+    let program = compile(&pattern, &topo).unwrap();
+    assert_eq!(program.cycles(), 10 * 64 + 2 * 64 + 64);
+
+    // A stuck-at-1 cell at address 5 first fails the r0 of ⇑(r0, w1).
+    let fault = Fault::StuckAt {
+        cell: Cell { addr: 5, bit: 0 },
+        value: true,
+    };
+    let software = execute(
+        FixedPattern::with_topology(program.pattern(), topo, 1),
+        FaultyExecutor::new(size, vec![fault]),
+    )
+    .unwrap_err()
+    .errors[0];
+    let outcome = BistOutcome {
+        fail: true,
+        fail_cycle: 64 + 2 * 5,
+        expected: 0,
+        received: 1,
+        signature: 0,
+    };
+    let ideal = simulate(
+        &program,
+        &topo,
+        &BistRunConfig::default(),
+        &mut IdealExecutor::new(size),
+    );
+    let hardware = ideal.trace.errors(&topo, &outcome).unwrap_err();
+    assert_eq!(hardware.outcome, outcome);
+    assert_eq!(hardware.located, Some(software));
+
+    // A failure cycle outside the trace, or on a write, is reported unlocated.
+    for fail_cycle in [program.cycles() as u32, 64 + 2 * 5 + 1, u32::MAX] {
+        let glitched = BistOutcome {
+            fail_cycle,
+            ..outcome
+        };
+        let failure = ideal.trace.errors(&topo, &glitched).unwrap_err();
+        assert_eq!(failure.outcome, glitched);
+        assert_eq!(failure.located, None);
+    }
+
+    let loc = program.locate(&topo, 12 * 64 + 3).unwrap();
+    assert_eq!((loc.element, loc.addr), (7, None));
+    assert_eq!(program.locate(&topo, program.cycles()), None);
+}
+
+#[cfg(feature = "unverified-bist")]
+#[test]
+fn bist_golden_model() {
+    // The model is unverified against the RTL, so this only checks that it
+    // agrees with the software patterns and with itself.
+    let mut lfsr = Lfsr::new(1 << 57);
+    lfsr.step();
+    assert_eq!(lfsr.state(), 1 << 58 | 1);
+    let mut lfsr = Lfsr::new(0);
+    lfsr.step();
+    assert_eq!(lfsr.state(), 0);
+    let mut misr = Misr::new(0x8000_0000);
+    misr.fold(1);
+    assert_eq!(misr.signature(), 0x0040_0006);
+
+    let topo = SramTopology::stac(2);
+    let size = topo.size();
+    let pattern = Pattern::march_cm().then(Pattern::rand(size.depth() as u64));
+    let program = compile(&pattern, &topo).unwrap();
+    let run = |config: &BistRunConfig, faults: Vec<Fault>| {
+        simulate(
+            &program,
+            &topo,
+            config,
+            &mut FaultyExecutor::new(size, faults),
+        )
+    };
+
+    // The march elements run exactly the ops of the software pattern.
+    let config = BistRunConfig::default();
+    let ideal = run(&config, vec![]);
+    assert!(!ideal.outcome.fail);
+    assert_eq!(ideal.trace.ops().len(), 10 * 64 + 2 * 64 + 64);
+    let software: Vec<FixedSramOp> = FixedPattern::with_topology(program.pattern(), topo, 1)
+        .ops()
+        .collect();
+    for (traced, op) in ideal.trace.ops().iter().zip(&software).take(10 * 64) {
+        assert_eq!(traced.op, *op);
+    }
+
+    // Random address elements visit addresses chosen by the generator.
+    let (idx, traced) = ideal.trace.at_cycle(12 * 64 + 3).unwrap();
+    assert_eq!((idx, traced.element), (12 * 64 + 3, 7));
+    let addrs: Vec<SramAddr> = ideal.trace.ops()[12 * 64..]
+        .iter()
+        .filter_map(|traced| traced.op.addr())
+        .collect();
+    assert!(addrs.iter().all(|&addr| addr < size.depth()));
+    assert!(addrs.iter().any(|&addr| addr != addrs[0]));
+    let reseeded = run(
+        &BistRunConfig {
+            rand_seed: 2,
+            ..config
+        },
+        vec![],
+    );
+    assert_ne!(reseeded.outcome.signature, ideal.outcome.signature);
+
+    // Faults show up in the failure cycle and the signature.
+    let fault = Fault::StuckAt {
+        cell: Cell { addr: 5, bit: 0 },
+        value: true,
+    };
+    let faulty = run(
+        &BistRunConfig {
+            stop_on_failure: false,
+            ..config
+        },
+        vec![fault],
+    );
+    assert!(faulty.outcome.fail);
+    assert_eq!(faulty.outcome.fail_cycle, 64 + 2 * 5);
+    assert_ne!(faulty.outcome.signature, ideal.outcome.signature);
+    let error = faulty
+        .trace
+        .errors(&topo, &faulty.outcome)
+        .unwrap_err()
+        .located
+        .unwrap();
+    assert_eq!(error.addr(), 5);
+}
+
+#[cfg(feature = "unverified-bist")]
+#[test]
+fn bist_cross_check() {
+    // Both runs use the model, so this only checks how their failures are
+    // attributed.
+    let topo = SramTopology::stac(2);
+    let size = topo.size();
+    let program = compile(&Pattern::march_cm(), &topo).unwrap();
+    let config = BistRunConfig::default();
+    let run = |faults: Vec<Fault>| {
+        simulate(
+            &program,
+            &topo,
+            &config,
+            &mut FaultyExecutor::new(size, faults),
+        )
+    };
+    let ideal = run(vec![]);
+    let check = |host: Vec<Fault>, bist: Vec<Fault>| {
+        let outcome = run(bist).outcome;
+        let host = ideal.trace.replay(FaultyExecutor::new(size, host));
+        CrossCheck::new(&topo, &ideal.trace, host, &outcome, ideal.outcome.signature)
+    };
+    let stuck_at = |addr, bit| Fault::StuckAt {
+        cell: Cell { addr, bit },
+        value: true,
+    };
+
+    let clean = check(vec![], vec![]);
+    assert!(clean.agrees());
+    assert_eq!(clean.signature_matches_model(), Some(true));
+
+    // A defect seen by both runs, and one only seen over the host path.
+    let mixed = check(vec![stuck_at(5, 0), stuck_at(1, 1)], vec![stuck_at(5, 0)]);
+    assert!(!mixed.agrees());
+    assert_eq!(mixed.signature_matches_model(), None);
+    assert_eq!(
+        mixed.array.iter().map(|err| err.op()).collect::<Vec<_>>(),
+        vec![64 + 2 * 5]
+    );
+    assert_eq!(
+        mixed
+            .host_only
+            .iter()
+            .map(|err| err.addr())
+            .collect::<Vec<_>>(),
+        vec![1]
+    );
+    assert_eq!(mixed.bist_only, None);
+    assert!(mixed.unchecked.iter().all(|err| err.op() > 64 + 2 * 5));
+
+    // A defect only seen at speed.
+    let at_speed = check(vec![], vec![stuck_at(5, 0)]);
+    assert!(!at_speed.agrees());
+    assert_eq!(at_speed.bist_only.map(|err| err.addr()), Some(5));
+
+    // A failure the trace cannot locate is never attributed.
+    let glitched = BistOutcome {
+        fail_cycle: u32::MAX,
+        ..run(vec![stuck_at(5, 0)]).outcome
+    };
+    let host = ideal
+        .trace
+        .replay(FaultyExecutor::new(size, vec![stuck_at(5, 0)]));
+    let unlocated = CrossCheck::new(&topo, &ideal.trace, host, &glitched, 0);
+    assert!(!unlocated.agrees());
+    assert_eq!(unlocated.unlocated, Some(glitched));
+    assert!(unlocated.array.is_empty() && unlocated.host_only.is_empty());
+    assert!(!unlocated.unchecked.is_empty());
+}
+
+#[cfg(feature = "unverified-bist")]
+#[test]
+fn bist_compile_limits() {
+    let topo = SramTopology::stac(2);
+    let size = topo.size();
+    let compile_err = |pattern: Pattern| compile(&pattern, &topo).unwrap_err();
+    assert_eq!(
+        compile_err(Pattern::march_cm().word_oriented(size)),
+        BistCompileError::TooManyElements { elements: 36 }
+    );
+    assert_eq!(
+        compile_err(Pattern::hammer(4)),
+        BistCompileError::UnsupportedOp {
+            element: 0,
+            op: SramOp::Repeat {
+                op: RepeatOp::Write {
+                    data: SramInput::Fixed(0),
+                    mask: SramInput::Fixed(u64::MAX),
+                },
+                count: 1,
+            },
+        }
+    );
+    assert_eq!(
+        compile_err(Pattern::mats_plus().then(Pattern::mats_plus().map_data(|_| SramInput::Rand))),
+        BistCompileError::UnknownReadData { element: 4 }
+    );
+    assert_eq!(
+        compile_err(
+            Pattern::builder()
+                .up([SramOp::write(SramInput::Fixed(0))])
+                .element(Element::new(
+                    AddrSeq::RowFast { descending: false },
+                    [SramOp::Read]
+                ))
+                .build()
+        ),
+        BistCompileError::MixedInnerDim { element: 1 }
+    );
+    assert_eq!(
+        compile_err(Pattern::march_cm().within(AddrScope::Range { start: 0, end: 4 })),
+        BistCompileError::UnsupportedScope { element: 0 }
+    );
+    assert_eq!(
+        compile_err(Pattern::retention(Duration::from_micros(10))),
+        BistCompileError::UnsupportedPause { element: 1 }
+    );
+    assert!(matches!(
+        compile(
+            &Pattern::march_cm(),
+            &SramTopology::new(SramSize::new(64, 64, 8), 4),
+        ),
+        Err(BistCompileError::UnsupportedSram { .. })
+    ));
+}
+
+/// An SRAM whose write mask decoder also enables lane `to` whenever lane
+/// `from` is enabled.
+struct MaskDecoderFaultExecutor {
+    inner: IdealExecutor,
+    from: u32,
+    to: u32,
+}
+
+impl Executor for MaskDecoderFaultExecutor {
+    fn init(&mut self) {}
+    fn read(&mut self, addr: SramAddr) -> SramWord {
+        self.inner.read(addr)
+    }
+    fn write(&mut self, addr: SramAddr, data: SramWord, mask: SramWord) {
+        let mask = if mask & (1 << self.from) != 0 {
+            mask | (1 << self.to)
+        } else {
+            mask
+        };
+        self.inner.write(addr, data, mask);
+    }
+    fn finish(&mut self) {}
+}
+
+#[test]
+fn lane_patterns() {
+    let size = SramSize::new(32, 16, 4);
+    let patterns = [
+        Pattern::walking_lanes(size),
+        Pattern::complementary_lanes(size),
+        Pattern::rand_lanes(256),
+    ];
+    for pattern in patterns.iter() {
+        execute(
+            FixedPattern::new(pattern.clone(), size, 1),
+            IdealExecutor::new(size),
+        )
+        .expect("lane pattern failed on ideal SRAM");
+        let faulty = MaskDecoderFaultExecutor {
+            inner: IdealExecutor::new(size),
+            from: 1,
+            to: 3,
+        };
+        execute(FixedPattern::new(pattern.clone(), size, 1), faulty)
+            .expect_err("lane pattern should detect mask decoder fault");
+    }
+    // March C- only performs full-word writes.
+    let faulty = MaskDecoderFaultExecutor {
+        inner: IdealExecutor::new(size),
+        from: 1,
+        to: 3,
+    };
+    execute(FixedPattern::new(Pattern::march_cm(), size, 1), faulty)
+        .expect("March C- cannot detect mask decoder faults");
+}
+
 #[test]
 #[ignore = "requires test chip"]
 fn mats_plus_bebe_scratchpad() {
@@ -61,8 +1234,607 @@ fn rand_bebe_scratchpad() {
     execute(pat, ex).expect("failed to run random pattern");
 }
 
+#[cfg(feature = "unverified-bist")]
+#[test]
+#[ignore = "requires test chip"]
+fn march_cm_bist_engine() {
+    let mut ex = BebeBistExecutor::new(2);
+    ex.execute(&Pattern::march_cm())
+        .expect("failed to run March C- on BIST engine");
+}
+
+#[cfg(feature = "unverified-bist")]
+#[test]
+#[ignore = "requires test chip"]
+fn march_cm_cross_check() {
+    let mut bist = BebeBistExecutor::new(2);
+    let check = bist
+        .cross_check(&Pattern::march_cm(), BebeExecutor::new(2))
+        .unwrap();
+    assert!(check.agrees(), "host path and BIST engine disagree");
+}
+
 #[test]
 #[ignore = "requires test chip"]
 fn sweep_tdc_sram_2() {
-    sweep_tdc_test(2, 0, 127);
+    sweep_tdc_test(&mut SramBist::new(Bebe), 2, 0, 127).unwrap();
+}
+
+#[cfg(all(feature = "tsi", feature = "unverified-bist"))]
+#[test]
+#[ignore = "requires test chip"]
+fn march_cm_bist_pins() {
+    use tsi::stac::StacController;
+    use tsi::Tsi;
+
+    let tty = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/ttyUSB1")
+        .expect("failed to open FPGA TTY");
+    let mut controller = StacController::new(Tsi::new(tty));
+    BebeBistExecutor::new(2)
+        .execute_external(
+            &Pattern::march_cm(),
+            &mut controller,
+            Duration::from_secs(10),
+        )
+        .expect("failed to run March C- on BIST engine via StacController pins");
+}
+
+/// A TSI device that accepts requests but never responds, like a serial
+/// port with a read timeout and no FPGA behind it.
+#[cfg(feature = "tsi")]
+struct SilentTsiDevice;
+
+#[cfg(feature = "tsi")]
+impl std::io::Write for SilentTsiDevice {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tsi")]
+impl std::io::Read for SilentTsiDevice {
+    fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+        Err(std::io::ErrorKind::TimedOut.into())
+    }
+}
+
+/// The receiving end of a fake UART.
+#[cfg(feature = "tsi")]
+struct FakeUartRx(std::sync::mpsc::Receiver<u8>);
+
+#[cfg(feature = "tsi")]
+impl std::io::Read for FakeUartRx {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.0.recv() {
+            Ok(b) => {
+                buf[0] = b;
+                Ok(1)
+            }
+            Err(_) => Ok(0),
+        }
+    }
+}
+
+/// A bebe bootloader behind a fake UART, which sends its beacon if `awake`.
+#[cfg(feature = "tsi")]
+struct FakeBebe {
+    tx: std::sync::mpsc::Sender<u8>,
+    mem: std::collections::HashMap<u64, u64>,
+    request: Vec<u8>,
+    nocked: bool,
+    /// Drops writes to the scratchpad.
+    broken_scratchpad: bool,
+}
+
+#[cfg(feature = "tsi")]
+impl FakeBebe {
+    fn uart(awake: bool, broken_scratchpad: bool) -> crate::bebe::BebeUart<Self> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        if awake {
+            for _ in 0..3 {
+                tx.send(b'A').unwrap();
+            }
+        }
+        let bebe = FakeBebe {
+            tx,
+            mem: Default::default(),
+            request: Vec::new(),
+            nocked: false,
+            broken_scratchpad,
+        };
+        crate::bebe::BebeUart::new(FakeUartRx(rx), bebe)
+    }
+}
+
+#[cfg(feature = "tsi")]
+impl std::io::Write for FakeBebe {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        use crate::bebe::{BEBE_NOCK_MAGIC, SCRATCHPAD_BASE_ADDR};
+        self.request.extend_from_slice(buf);
+        let be = |bytes: &[u8]| bytes.iter().fold(0u64, |acc, &b| acc << 8 | b as u64);
+        loop {
+            if !self.nocked {
+                if self.request.len() < BEBE_NOCK_MAGIC.len() {
+                    break;
+                }
+                assert_eq!(&self.request[..BEBE_NOCK_MAGIC.len()], BEBE_NOCK_MAGIC);
+                self.request.drain(..BEBE_NOCK_MAGIC.len());
+                self.nocked = true;
+                // A beacon sent before the nock was handled.
+                self.tx.send(b'A').unwrap();
+                self.tx.send(b'Y').unwrap();
+            } else if self.request.len() >= 13 && self.request[0] == b'R' {
+                assert_eq!(be(&self.request[1..5]), 8);
+                let addr = be(&self.request[5..13]);
+                let value = self.mem.get(&addr).copied().unwrap_or(0);
+                for b in value.to_be_bytes() {
+                    self.tx.send(b).unwrap();
+                }
+                self.request.drain(..13);
+            } else if self.request.len() >= 21 && self.request[0] == b'W' {
+                assert_eq!(be(&self.request[1..5]), 8);
+                let addr = be(&self.request[5..13]);
+                if !(self.broken_scratchpad && addr >= SCRATCHPAD_BASE_ADDR) {
+                    self.mem.insert(addr, be(&self.request[13..21]));
+                }
+                self.tx.send(b'Y').unwrap();
+                self.request.drain(..21);
+            } else {
+                break;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tsi")]
+#[test]
+fn board_bringup() {
+    use crate::bringup::{BoardRevision, Bringup, BringupState, Step};
+    use tsi::fake::FakeTsiDevice;
+    use tsi::stac::StacController;
+    use tsi::Tsi;
+
+    let controller = || StacController::new(Tsi::new(FakeTsiDevice::default()));
+    let mut sequence = BoardRevision::StacV1.sequence();
+    for (_, timeout) in sequence.steps.iter_mut() {
+        *timeout = Duration::from_millis(200);
+    }
+
+    let mut bringup = Bringup::new(
+        controller(),
+        FakeBebe::uart(true, false),
+        BoardRevision::StacV1.sequence(),
+    );
+    assert_eq!(bringup.state(), &BringupState::Pending(0));
+    bringup.run().expect("bring-up should succeed");
+    assert_eq!(bringup.state(), &BringupState::Ready);
+    let (mut fpga, _) = bringup.into_inner();
+    let status = fpga.status().unwrap();
+    assert!(status.clk_en);
+    assert_eq!(status.half_clk_div_ratio, 125);
+
+    let mut bringup = Bringup::new(controller(), FakeBebe::uart(false, false), sequence.clone());
+    let err = bringup.run().unwrap_err();
+    assert_eq!(err.step, Step::WaitBeacon);
+    assert!(err.diagnosis.starts_with("no beacon"), "{err}");
+
+    let mut bringup = Bringup::new(controller(), FakeBebe::uart(true, true), sequence);
+    let err = bringup.run().unwrap_err();
+    assert_eq!(err.step, Step::CheckScratchpad { words: 4 });
+    assert!(
+        err.diagnosis.starts_with("scratchpad readback mismatch"),
+        "{err}"
+    );
+    assert!(matches!(bringup.state(), BringupState::Failed(_)));
+
+    // A silent FPGA fails the first step that reads from it.
+    let silent = StacController::new(Tsi::new(SilentTsiDevice));
+    let sequence = BoardRevision::StacV1.sequence();
+    let mut bringup = Bringup::new(silent, FakeBebe::uart(true, false), sequence);
+    let err = bringup.run().unwrap_err();
+    assert_eq!(err.step, Step::CheckController);
+    assert!(err.diagnosis.starts_with("no response from FPGA"), "{err}");
+}
+
+#[cfg(feature = "tsi")]
+#[test]
+fn bringup_sequence_file() {
+    use crate::bringup::{BoardRevision, BringupSequence, Step};
+
+    let stac_v1 = "
+        # STAC-V1
+        reset 1000
+        set-clock 200000 1000
+        enable-clock 1000
+        check-controller 1000
+        wait-beacon 5000 # the bootloader starts after the clock
+        nock 3000
+
+        check-scratchpad 4 10000
+        check-sram-bist 10000
+    ";
+    assert_eq!(
+        stac_v1.parse::<BringupSequence>().unwrap(),
+        BoardRevision::StacV1.sequence()
+    );
+
+    // A new revision needs only a new file.
+    let sequence: BringupSequence = "set-clock 400000 1000\npll-reset 10 2000".parse().unwrap();
+    assert_eq!(
+        sequence.steps,
+        vec![
+            (Step::SetClock { hz: 400_000 }, Duration::from_secs(1)),
+            (
+                Step::PllReset {
+                    hold: Duration::from_millis(10)
+                },
+                Duration::from_secs(2)
+            ),
+        ]
+    );
+
+    for (file, err) in [
+        ("reset", "line 1: expected a step"),
+        ("reset 1 1000", "line 1: reset takes no parameter"),
+        ("\nset-clock 1000", "line 2: set-clock needs a parameter"),
+        ("nock 3s", "line 1: \"3s\" is not a number"),
+        ("boot 1000", "line 1: unknown step \"boot\""),
+    ] {
+        let parsed = file.parse::<BringupSequence>().unwrap_err();
+        assert!(
+            parsed.starts_with(err),
+            "{parsed:?} should start with {err:?}"
+        );
+    }
+}
+
+#[test]
+fn register_maps() {
+    use crate::regs::stac_controller;
+    use bitvec::prelude::*;
+
+    let offsets = |regs: &[Reg]| -> Vec<(&str, u64)> {
+        regs.iter().map(|reg| (reg.name(), reg.offset())).collect()
+    };
+    let sram_bist = offsets(sram_bist::ALL);
+    assert_eq!(sram_bist.len(), 27);
+    for (name, offset) in [
+        ("TDC", 0x48),
+        ("DONE", 0x68),
+        ("BIST_ELEMENT_SEQUENCE", 0xa0),
+        ("BIST_PATTERN_TABLE", 0x120),
+        ("BIST_SIGNATURE", 0x178),
+        ("EX", 0x180),
+    ] {
+        assert!(sram_bist.contains(&(name, offset)), "{name} at {offset:#x}");
+    }
+    assert_eq!(
+        offsets(stac_controller::ALL).last(),
+        Some(&("HALF_CLK_DIV_RATIO", 0x50))
+    );
+    assert_eq!(stac_controller::CLK_EN.addr(), 0x9000_0048);
+
+    // Wide registers span several words, least significant first.
+    let reg = sram_bist::TDC;
+    assert_eq!(reg.words(), 4);
+    assert_eq!(
+        reg.word_addrs().collect::<Vec<_>>(),
+        vec![0x1048, 0x1050, 0x1058, 0x1060]
+    );
+    let mut value = bitvec![u64, Lsb0; 0; 252];
+    value.set(63, true);
+    value.set(64, true);
+    value.set(251, true);
+    let words = reg.pack(&value);
+    assert_eq!(words, vec![1 << 63, 1, 0, 1 << 59]);
+    assert_eq!(reg.unpack(&words), value);
+
+    // Values are zero-extended or truncated to the register's width.
+    assert_eq!(sram_bist::BIST_RAND_SEED.pack(1u64.view_bits()), vec![1, 0]);
+    assert_eq!(sram_bist::WE.pack(u64::MAX.view_bits()), vec![1]);
+    assert_eq!(
+        sram_bist::DOUT.unpack(&[u64::MAX]).load_le::<u64>(),
+        0xffff_ffff
+    );
+}
+
+/// The `tsi` crate keeps its own offsets, which must match the RTL.
+#[cfg(feature = "tsi")]
+#[test]
+fn stac_controller_offsets() {
+    use crate::regs::stac_controller;
+    use tsi::stac::{regs, STAC_CONTROLLER_BASE};
+
+    assert_eq!(STAC_CONTROLLER_BASE, stac_controller::BASE);
+    for (offset, reg) in [
+        (regs::SRAM_EXT_EN, stac_controller::SRAM_EXT_EN),
+        (regs::SRAM_SCAN_MODE, stac_controller::SRAM_SCAN_MODE),
+        (regs::SRAM_EN, stac_controller::SRAM_EN),
+        (regs::SRAM_BIST_EN, stac_controller::SRAM_BIST_EN),
+        (regs::SRAM_BIST_START, stac_controller::SRAM_BIST_START),
+        (regs::PLL_SEL, stac_controller::PLL_SEL),
+        (regs::PLL_SCAN_RSTN, stac_controller::PLL_SCAN_RSTN),
+        (regs::PLL_ARSTB, stac_controller::PLL_ARSTB),
+        (regs::SRAM_BIST_DONE, stac_controller::SRAM_BIST_DONE),
+        (regs::CLK_EN, stac_controller::CLK_EN),
+        (
+            regs::HALF_CLK_DIV_RATIO,
+            stac_controller::HALF_CLK_DIV_RATIO,
+        ),
+    ] {
+        assert_eq!(offset, reg.offset(), "{}", reg.name());
+    }
+}
+
+/// An SramBist peripheral backed by the golden model, recording every
+/// register write. Clones share the same peripheral.
+#[derive(Clone)]
+struct FakeSramBist {
+    state: std::rc::Rc<std::cell::RefCell<FakeSramBistState>>,
+}
+
+struct FakeSramBistState {
+    regs: std::collections::HashMap<u64, u64>,
+    writes: Vec<u64>,
+    sram: FaultyExecutor,
+    /// A value `DONE` is stuck at, in which case nothing runs.
+    done_stuck: Option<bool>,
+    /// Whether every bus access fails.
+    offline: bool,
+}
+
+impl FakeSramBist {
+    /// A peripheral whose SRAM 2 has the given faults.
+    fn new(faults: Vec<Fault>) -> Self {
+        let state = FakeSramBistState {
+            regs: Default::default(),
+            writes: Vec::new(),
+            sram: FaultyExecutor::new(SramTopology::stac(2).size(), faults),
+            done_stuck: None,
+            offline: false,
+        };
+        Self {
+            state: std::rc::Rc::new(std::cell::RefCell::new(state)),
+        }
+    }
+
+    /// Makes `DONE` read as `value` from now on.
+    fn stick_done(&self, value: bool) {
+        self.state.borrow_mut().done_stuck = Some(value);
+    }
+
+    /// Makes every bus access fail from now on.
+    fn disconnect(&self) {
+        self.state.borrow_mut().offline = true;
+    }
+
+    /// The number of bus writes to `reg`.
+    fn writes(&self, reg: Reg) -> usize {
+        let addrs: Vec<u64> = reg.word_addrs().collect();
+        let state = self.state.borrow();
+        state
+            .writes
+            .iter()
+            .filter(|addr| addrs.contains(addr))
+            .count()
+    }
+}
+
+impl FakeSramBistState {
+    fn reg(&self, reg: Reg) -> u64 {
+        self.words(reg)[0]
+    }
+
+    fn words(&self, reg: Reg) -> Vec<u64> {
+        reg.word_addrs()
+            .map(|addr| self.regs.get(&addr).copied().unwrap_or(0))
+            .collect()
+    }
+
+    fn set(&mut self, reg: Reg, value: u64) {
+        self.regs.insert(reg.addr(), value);
+    }
+
+    fn execute(&mut self) {
+        assert_eq!(self.reg(sram_bist::SRAM_ID), 2, "only SRAM 2 is modelled");
+        let addr = self.reg(sram_bist::ADDR) as SramAddr;
+        if self.reg(sram_bist::SRAM_SEL) == 0 {
+            if self.reg(sram_bist::WE) == 1 {
+                let (data, mask) = (self.reg(sram_bist::DIN), self.reg(sram_bist::MASK));
+                self.sram.write(addr, data, mask);
+            } else {
+                let dout = self.sram.read(addr);
+                self.set(sram_bist::DOUT, dout);
+            }
+        } else {
+            #[cfg(feature = "unverified-bist")]
+            self.run_bist();
+        }
+        self.set(sram_bist::DONE, 1);
+    }
+
+    /// Runs the BIST engine on the model, standing in for the hardware.
+    #[cfg(feature = "unverified-bist")]
+    fn run_bist(&mut self) {
+        let regs = BistRegisters {
+            element_sequence: self.words(sram_bist::BIST_ELEMENT_SEQUENCE)
+                [..ELEMENT_SEQUENCE_WORDS]
+                .try_into()
+                .unwrap(),
+            pattern_table: self.words(sram_bist::BIST_PATTERN_TABLE)[..PATTERN_TABLE_WORDS]
+                .try_into()
+                .unwrap(),
+            max_element_idx: self.reg(sram_bist::BIST_MAX_ELEMENT_IDX),
+            max_row_addr: self.reg(sram_bist::BIST_MAX_ROW_ADDR),
+            max_col_addr: self.reg(sram_bist::BIST_MAX_COL_ADDR),
+            inner_dim: self.reg(sram_bist::BIST_INNER_DIM),
+        };
+        let seed = self.words(sram_bist::BIST_RAND_SEED);
+        let config = BistRunConfig {
+            rand_seed: seed[0] as u128 | (seed[1] as u128) << 64,
+            sig_seed: self.reg(sram_bist::BIST_SIG_SEED) as u32,
+            cycle_limit: self.reg(sram_bist::BIST_CYCLE_LIMIT) as u32,
+            stop_on_failure: self.reg(sram_bist::BIST_STOP_ON_FAILURE) == 1,
+        };
+        let program = BistProgram::decode(&regs).expect("invalid BIST registers");
+        let outcome = simulate(&program, &SramTopology::stac(2), &config, &mut self.sram).outcome;
+        self.set(sram_bist::BIST_FAIL, outcome.fail as u64);
+        self.set(sram_bist::BIST_FAIL_CYCLE, outcome.fail_cycle as u64);
+        self.set(sram_bist::BIST_EXPECTED, outcome.expected);
+        self.set(sram_bist::BIST_RECEIVED, outcome.received);
+        self.set(sram_bist::BIST_SIGNATURE, outcome.signature as u64);
+    }
+}
+
+impl Transport for FakeSramBist {
+    fn read(&mut self, addr: u64) -> std::io::Result<u64> {
+        let state = self.state.borrow();
+        if state.offline {
+            return Err(std::io::ErrorKind::TimedOut.into());
+        }
+        Ok(match state.done_stuck {
+            Some(done) if addr == sram_bist::DONE.addr() => done as u64,
+            _ => state.regs.get(&addr).copied().unwrap_or(0),
+        })
+    }
+
+    /// Operations finish as soon as `EX` is written, before `DONE` can be
+    /// polled, as they do on the chip.
+    fn write(&mut self, addr: u64, data: u64) -> std::io::Result<()> {
+        let mut state = self.state.borrow_mut();
+        if state.offline {
+            return Err(std::io::ErrorKind::TimedOut.into());
+        }
+        state.writes.push(addr);
+        state.regs.insert(addr, data);
+        if addr == sram_bist::EX.addr() {
+            state.set(sram_bist::DONE, 0);
+            state.execute();
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn sram_bist_driver() {
+    let size = SramTopology::stac(2).size();
+    let fault = Fault::StuckAt {
+        cell: Cell { addr: 5, bit: 0 },
+        value: true,
+    };
+
+    // Configuration registers are written once per test, not once per op.
+    let fake = FakeSramBist::new(vec![]);
+    let ex = MmioExecutor::with_driver(SramBist::new(fake.clone()), 2);
+    execute(FixedPattern::new(Pattern::march_cm(), size, 1), ex)
+        .expect("March C- should pass on a fault-free SRAM");
+    assert_eq!(fake.writes(sram_bist::SRAM_ID), 1);
+    assert_eq!(fake.writes(sram_bist::SRAM_SEL), 1);
+    assert_eq!(fake.writes(sram_bist::EX), 10 * size.depth() as usize);
+
+    let software = execute(
+        FixedPattern::new(Pattern::march_cm(), size, 1),
+        FaultyExecutor::new(size, vec![fault]),
+    );
+    let fake = FakeSramBist::new(vec![fault]);
+    let ex = MmioExecutor::with_driver(SramBist::new(fake), 2);
+    assert_eq!(
+        execute(FixedPattern::new(Pattern::march_cm(), size, 1), ex),
+        software
+    );
+
+    // Operations time out if DONE never rises.
+    let fake = FakeSramBist::new(vec![]);
+    fake.stick_done(false);
+    let mut bist = SramBist::new(fake).with_timeout(Duration::from_millis(10));
+    bist.select_sram(2).unwrap();
+    let err = bist.read(0).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+    // Transport errors are returned rather than panicking.
+    let fake = FakeSramBist::new(vec![]);
+    let mut bist = SramBist::new(fake.clone());
+    bist.select_sram(2).unwrap();
+    fake.disconnect();
+    assert!(bist.write(0, 1, u64::MAX).is_err());
+    assert!(bist.read(0).is_err());
+}
+
+#[cfg(feature = "unverified-bist")]
+#[test]
+fn bist_executor_driver() {
+    let size = SramTopology::stac(2).size();
+    let fault = Fault::StuckAt {
+        cell: Cell { addr: 5, bit: 0 },
+        value: true,
+    };
+
+    // The BIST engine is only reprogrammed with what changed.
+    let fake = FakeSramBist::new(vec![]);
+    let mut bist = BistExecutor::with_driver(SramBist::new(fake.clone()), 2);
+    bist.execute(&Pattern::march_cm())
+        .expect("March C- should pass on a fault-free SRAM");
+    bist.execute(&Pattern::march_cm()).unwrap();
+    assert_eq!(fake.writes(sram_bist::BIST_ELEMENT_SEQUENCE), 16);
+    assert_eq!(fake.writes(sram_bist::EX), 2);
+
+    // Failures come back as the errors a host path run reports, with the
+    // raw results alongside.
+    let fake = FakeSramBist::new(vec![fault]);
+    let mut bist = BistExecutor::with_driver(SramBist::new(fake), 2);
+    match bist.execute(&Pattern::march_cm()).unwrap_err() {
+        BistRunError::Failed { errors, outcome } => {
+            assert!(outcome.fail);
+            assert_eq!(errors.errors.len(), 1);
+            assert_eq!(errors.errors[0].addr(), 5);
+        }
+        err => panic!("expected a BIST failure, got {err}"),
+    }
+
+    // Patterns that cannot be compiled, and runs that never finish, are
+    // errors rather than panics.
+    let fake = FakeSramBist::new(vec![]);
+    let mut bist = BistExecutor::with_driver(SramBist::new(fake.clone()), 2);
+    let err = bist
+        .execute(&Pattern::retention(Duration::from_millis(1)))
+        .unwrap_err();
+    assert!(matches!(err, BistRunError::Compile(_)));
+    fake.stick_done(false);
+    let mut bist = BistExecutor::with_driver(
+        SramBist::new(fake).with_timeout(Duration::from_millis(10)),
+        2,
+    );
+    match bist.execute(&Pattern::march_cm()).unwrap_err() {
+        BistRunError::Io(err) => assert_eq!(err.kind(), std::io::ErrorKind::TimedOut),
+        err => panic!("expected a timeout, got {err}"),
+    }
+
+    // Host and BIST runs of the same SRAM agree on an array defect. The fake
+    // runs the model, so this only checks the plumbing.
+    let fake = FakeSramBist::new(vec![fault]);
+    let mut bist = BistExecutor::with_driver(SramBist::new(fake.clone()), 2);
+    let host = MmioExecutor::with_driver(SramBist::new(fake.clone()), 2);
+    let random = Pattern::march_cm().then(Pattern::rand(size.depth() as u64));
+    match bist.cross_check(&random, host).unwrap_err() {
+        BistRunError::Random(err) => assert_eq!(err, RandomElementError { element: 7 }),
+        err => panic!("expected a random element error, got {err}"),
+    }
+    assert_eq!(fake.writes(sram_bist::EX), 0);
+    let host = MmioExecutor::with_driver(SramBist::new(fake), 2);
+    let check = bist.cross_check(&Pattern::march_cm(), host).unwrap();
+    assert!(check.agrees());
+    assert_eq!(check.array.len(), 1);
+    assert_eq!(check.array[0].addr(), 5);
 }
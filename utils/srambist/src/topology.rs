@@ -0,0 +1,233 @@
+use crate::fault::Cell;
+use crate::pattern::{Background, SramAddr, SramSize, SramWord};
+use serde::{Deserialize, Serialize};
+
+/// Where the column select bits sit in a logical address.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum ColumnBits {
+    /// The low `log2(mux)` address bits select the column; consecutive
+    /// addresses share a row.
+    Low,
+    /// The high `log2(mux)` address bits select the column; consecutive
+    /// addresses share a column.
+    High,
+}
+
+/// How the bits of a word are laid out across physical columns.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum BitLayout {
+    /// Bit `i` of every word in a row lives in the `i`-th group of `mux`
+    /// adjacent columns. This is the layout of column-muxed SRAM22 macros.
+    Interleaved,
+    /// The bits of a word occupy `width` adjacent columns.
+    Contiguous,
+}
+
+/// The physical location of a bit cell in the array.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
+pub struct PhysicalCell {
+    pub row: u32,
+    pub col: u32,
+}
+
+/// The physical organization of an SRAM macro.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub struct SramTopology {
+    size: SramSize,
+    mux: u32,
+    column_bits: ColumnBits,
+    bit_layout: BitLayout,
+}
+
+/// Parameters of the STAC test SRAMs, indexed by `SRAM_ID`, as
+/// `(mask granularity, mux ratio, words, data width)`.
+const STAC_SRAM_PARAMS: [(u32, u32, u32, u32); 8] = [
+    (8, 8, 2048, 32),
+    (8, 4, 256, 32),
+    (8, 4, 64, 32),
+    (24, 4, 64, 24),
+    (8, 8, 1024, 32),
+    (32, 8, 1024, 32),
+    (32, 4, 512, 32),
+    (8, 4, 512, 32),
+];
+
+/// The number of test SRAMs on STAC.
+pub const STAC_NUM_SRAMS: usize = STAC_SRAM_PARAMS.len();
+
+impl SramTopology {
+    /// An SRAM with `mux` columns per bit, using the SRAM22 address and bit layout.
+    pub fn new(size: SramSize, mux: u32) -> Self {
+        assert!(mux.is_power_of_two(), "mux ratio must be a power of 2");
+        assert_eq!(
+            size.depth() % mux,
+            0,
+            "SRAM depth must be an even multiple of mux ratio"
+        );
+        Self {
+            size,
+            mux,
+            column_bits: ColumnBits::Low,
+            bit_layout: BitLayout::Interleaved,
+        }
+    }
+
+    /// The topology of the STAC test SRAM with the given `SRAM_ID`.
+    pub fn stac(id: usize) -> Self {
+        assert!(id < STAC_NUM_SRAMS, "invalid STAC SRAM ID");
+        let (mask_gran, mux, words, width) = STAC_SRAM_PARAMS[id];
+        let size = SramSize::new(width as SramWord, words, (width / mask_gran) as SramWord);
+        Self::new(size, mux)
+    }
+
+    pub fn with_column_bits(mut self, column_bits: ColumnBits) -> Self {
+        self.column_bits = column_bits;
+        self
+    }
+
+    pub fn with_bit_layout(mut self, bit_layout: BitLayout) -> Self {
+        self.bit_layout = bit_layout;
+        self
+    }
+
+    pub fn size(&self) -> SramSize {
+        self.size
+    }
+    pub fn mux(&self) -> u32 {
+        self.mux
+    }
+    pub fn column_bits(&self) -> ColumnBits {
+        self.column_bits
+    }
+    pub fn bit_layout(&self) -> BitLayout {
+        self.bit_layout
+    }
+
+    /// The number of physical rows (wordlines).
+    pub fn rows(&self) -> u32 {
+        self.size.depth() / self.mux
+    }
+
+    /// The number of physical columns (bitline pairs).
+    pub fn cols(&self) -> u32 {
+        self.size.width() as u32 * self.mux
+    }
+
+    /// The physical row accessed by `addr`.
+    pub fn row(&self, addr: SramAddr) -> u32 {
+        assert!(addr < self.size.depth(), "addr out of bounds");
+        match self.column_bits {
+            ColumnBits::Low => addr / self.mux,
+            ColumnBits::High => addr % self.rows(),
+        }
+    }
+
+    /// The column mux select for `addr`.
+    pub fn col_sel(&self, addr: SramAddr) -> u32 {
+        assert!(addr < self.size.depth(), "addr out of bounds");
+        match self.column_bits {
+            ColumnBits::Low => addr % self.mux,
+            ColumnBits::High => addr / self.rows(),
+        }
+    }
+
+    /// The logical address of the given row and column mux select.
+    pub fn addr(&self, row: u32, col_sel: u32) -> SramAddr {
+        assert!(row < self.rows(), "row out of bounds");
+        assert!(col_sel < self.mux, "column select out of bounds");
+        match self.column_bits {
+            ColumnBits::Low => row * self.mux + col_sel,
+            ColumnBits::High => col_sel * self.rows() + row,
+        }
+    }
+
+    /// The physical location of a logical bit cell.
+    pub fn physical(&self, cell: Cell) -> PhysicalCell {
+        assert!(
+            (cell.bit as SramWord) < self.size.width(),
+            "bit out of bounds"
+        );
+        let col_sel = self.col_sel(cell.addr);
+        let col = match self.bit_layout {
+            BitLayout::Interleaved => cell.bit * self.mux + col_sel,
+            BitLayout::Contiguous => col_sel * self.size.width() as u32 + cell.bit,
+        };
+        PhysicalCell {
+            row: self.row(cell.addr),
+            col,
+        }
+    }
+
+    /// The logical bit cell at a physical location.
+    pub fn logical(&self, cell: PhysicalCell) -> Cell {
+        assert!(cell.col < self.cols(), "column out of bounds");
+        let width = self.size.width() as u32;
+        let (col_sel, bit) = match self.bit_layout {
+            BitLayout::Interleaved => (cell.col % self.mux, cell.col / self.mux),
+            BitLayout::Contiguous => (cell.col / width, cell.col % width),
+        };
+        Cell {
+            addr: self.addr(cell.row, col_sel),
+            bit,
+        }
+    }
+
+    /// The cells physically adjacent to `cell` in the same row or column.
+    pub fn neighbours(&self, cell: Cell) -> Vec<Cell> {
+        let PhysicalCell { row, col } = self.physical(cell);
+        let mut out = Vec::new();
+        if row > 0 {
+            out.push(PhysicalCell { row: row - 1, col });
+        }
+        if row + 1 < self.rows() {
+            out.push(PhysicalCell { row: row + 1, col });
+        }
+        if col > 0 {
+            out.push(PhysicalCell { row, col: col - 1 });
+        }
+        if col + 1 < self.cols() {
+            out.push(PhysicalCell { row, col: col + 1 });
+        }
+        out.into_iter().map(|cell| self.logical(cell)).collect()
+    }
+
+    /// The addresses of words with a cell physically adjacent to a cell of `addr`.
+    pub fn neighbour_addrs(&self, addr: SramAddr) -> Vec<SramAddr> {
+        let mut out: Vec<SramAddr> = (0..self.size.width() as u32)
+            .flat_map(|bit| self.neighbours(Cell { addr, bit }))
+            .map(|cell| cell.addr)
+            .filter(|&other| other != addr)
+            .collect();
+        out.sort_unstable();
+        out.dedup();
+        out
+    }
+
+    /// The physical locations of the bits that differ between `expected` and `received`.
+    pub fn failing_cells(
+        &self,
+        addr: SramAddr,
+        expected: SramWord,
+        received: SramWord,
+    ) -> Vec<PhysicalCell> {
+        let diff = expected ^ received;
+        (0..self.size.width() as u32)
+            .filter(|bit| diff & (1 << bit) != 0)
+            .map(|bit| self.physical(Cell { addr, bit }))
+            .collect()
+    }
+
+    /// The word stored at `addr` when the whole array holds the given background.
+    pub fn background(&self, kind: Background, addr: SramAddr) -> SramWord {
+        (0..self.size.width() as u32)
+            .filter(|&bit| {
+                let PhysicalCell { row, col } = self.physical(Cell { addr, bit });
+                match kind {
+                    Background::Checkerboard => (row + col) % 2 == 1,
+                    Background::RowStripe => row % 2 == 1,
+                    Background::ColStripe => col % 2 == 1,
+                }
+            })
+            .fold(0, |word, bit| word | (1 << bit))
+    }
+}
@@ -0,0 +1,66 @@
+//! Generates the `StacController` register offsets from the `REG_WIDTH` map
+//! of the Scala RTL, so that host code cannot drift from the hardware.
+//!
+//! Offsets are laid out as `REGMAP_OFFSET` does: each register takes as many
+//! 64-bit words as its width needs, in `REG_WIDTH` order.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+const CTRL_REGS: &str =
+    "../../generators/stac-controller/src/main/scala/StacControllerCtrlRegs.scala";
+
+const OBJECT: &str = "StacControllerCtrlRegs";
+
+fn main() {
+    println!("cargo:rerun-if-changed={CTRL_REGS}");
+    let scala = fs::read_to_string(CTRL_REGS)
+        .unwrap_or_else(|err| panic!("failed to read {CTRL_REGS}: {err}"));
+
+    let mut out = String::new();
+    let mut offset = 0;
+    for (name, width) in reg_widths(object_body(&scala, OBJECT)) {
+        writeln!(out, "pub const {name}: u64 = {offset:#x};").unwrap();
+        offset += ((width - 1) / 64 + 1) * 8;
+    }
+
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("regs.rs");
+    fs::write(dest, out).unwrap();
+}
+
+/// The text of `object NAME extends Enumeration { ... }`.
+fn object_body<'a>(scala: &'a str, object: &str) -> &'a str {
+    let start = scala
+        .find(&format!("object {object} "))
+        .unwrap_or_else(|| panic!("{object} not found in {CTRL_REGS}"));
+    let body = &scala[start..];
+    let end = body[1..]
+        .find("\nobject ")
+        .map_or(body.len(), |end| end + 1);
+    &body[..end]
+}
+
+/// The `NAME -> width` entries of `REG_WIDTH`, in order.
+fn reg_widths(body: &str) -> Vec<(String, u64)> {
+    let start = body
+        .find("REG_WIDTH = LinkedHashMap(")
+        .expect("REG_WIDTH not found");
+    let map = &body[start..];
+    let map = &map[map.find('(').unwrap() + 1..map.find(')').unwrap()];
+    map.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (name, width) = entry
+                .split_once("->")
+                .unwrap_or_else(|| panic!("malformed REG_WIDTH entry {entry:?}"));
+            let width = width
+                .trim()
+                .parse()
+                .unwrap_or_else(|_| panic!("malformed REG_WIDTH entry {entry:?}"));
+            (name.trim().to_string(), width)
+        })
+        .collect()
+}
@@ -4,7 +4,8 @@ use std::time::Duration;
 use clap::{Parser, Subcommand};
 use clap_num::maybe_hex;
 
-use tsi::write_req;
+use tsi::stac::StacController;
+use tsi::{write_req, Tsi};
 
 #[derive(Debug, Parser)]
 #[clap(name = "uarttsi", version)]
@@ -42,6 +43,13 @@ enum Command {
         #[clap(short='l', long, value_parser=maybe_hex::<usize>)]
         len: Option<usize>,
     },
+    /// Reads back the state of the FPGA's StacController.
+    Status,
+    /// Enables the chip clock at the frequency nearest to the given one.
+    Clock {
+        /// The desired chip clock frequency in Hz.
+        hz: u64,
+    },
 }
 
 fn main() {
@@ -71,5 +79,20 @@ fn main() {
             }
             write_req(&mut port, tsi::Command::Write, addr, &data);
         }
+        Command::Status => {
+            let mut controller = StacController::new(Tsi::new(port));
+            let status = controller.status().expect("failed to read StacController");
+            print!("{status}");
+        }
+        Command::Clock { hz } => {
+            let mut controller = StacController::new(Tsi::new(port));
+            let achieved = controller
+                .set_clock_hz(hz)
+                .expect("failed to set clock divider");
+            controller
+                .enable_clock(true)
+                .expect("failed to enable clock");
+            println!("Chip clock enabled at {achieved:.3} Hz");
+        }
     }
 }
@@ -0,0 +1,90 @@
+//! A fake TSI device, for testing code built on [`Tsi`](crate::Tsi) without
+//! an FPGA.
+
+use crate::stac::{regs, STAC_CONTROLLER_BASE};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+
+/// A TSI device with a flat 64-bit register space, whose `SRAM_BIST_DONE`
+/// rises after `SRAM_BIST_START` has been polled a few times, and falls
+/// when `SRAM_BIST_START` is lowered.
+#[derive(Debug, Default)]
+pub struct FakeTsiDevice {
+    regs: HashMap<u64, u64>,
+    writes: Vec<(u64, u64)>,
+    request: Vec<u8>,
+    response: VecDeque<u8>,
+    polls: u32,
+    done_stuck: bool,
+}
+
+impl FakeTsiDevice {
+    /// A device whose `SRAM_BIST_DONE` starts high and never falls.
+    pub fn with_bist_done_stuck() -> Self {
+        Self {
+            done_stuck: true,
+            ..Self::default()
+        }
+    }
+
+    /// Every write so far, as `(addr, value)`.
+    pub fn writes(&self) -> &[(u64, u64)] {
+        &self.writes
+    }
+}
+
+impl Write for FakeTsiDevice {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.request.extend_from_slice(buf);
+        let field = |req: &[u8], at: usize, len: usize| {
+            req[at..at + len]
+                .iter()
+                .rev()
+                .fold(0u64, |acc, &b| acc << 8 | b as u64)
+        };
+        while self.request.len() >= 20 {
+            let (cmd, addr) = (field(&self.request, 0, 4), field(&self.request, 4, 8));
+            let bytes = (field(&self.request, 12, 8) as usize + 1) * 4;
+            let done = STAC_CONTROLLER_BASE + regs::SRAM_BIST_DONE;
+            let start = STAC_CONTROLLER_BASE + regs::SRAM_BIST_START;
+            if cmd == 0 {
+                if addr == done && self.done_stuck {
+                    self.regs.insert(addr, 1);
+                } else if addr == done && self.regs.get(&start) == Some(&1) {
+                    self.polls += 1;
+                    self.regs.insert(addr, (self.polls >= 3) as u64);
+                }
+                let value = self.regs.get(&addr).copied().unwrap_or(0);
+                self.response
+                    .extend(value.to_le_bytes().iter().chain([0; 8].iter()).take(bytes));
+                self.request.drain(..20);
+            } else if self.request.len() >= 20 + bytes {
+                let value = field(&self.request, 20, bytes.min(8));
+                self.regs.insert(addr, value);
+                self.writes.push((addr, value));
+                if addr == start && value == 0 {
+                    self.polls = 0;
+                    self.regs.insert(done, 0);
+                }
+                self.request.drain(..20 + bytes);
+            } else {
+                break;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Read for FakeTsiDevice {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.response.len());
+        for (dst, src) in buf.iter_mut().zip(self.response.drain(..n)) {
+            *dst = src;
+        }
+        Ok(n)
+    }
+}
@@ -1,4 +1,12 @@
-use std::io::Write;
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant};
+
+#[cfg(any(test, feature = "fake"))]
+pub mod fake;
+pub mod stac;
+
+#[cfg(test)]
+mod tests;
 
 #[derive(Debug, Clone, Copy)]
 pub enum Command {
@@ -27,9 +35,7 @@ pub fn write_req<W: Write>(
     let num_words = std::cmp::max(data.len() / 4, 1);
     w.write_all(&(num_words - 1).to_le_bytes())?;
 
-    write_chunks(w, data);
-
-    Ok(())
+    write_chunks(w, data)
 }
 
 pub fn write_chunks<W: Write>(w: &mut W, data: &[u8]) -> std::io::Result<()> {
@@ -38,3 +44,74 @@ pub fn write_chunks<W: Write>(w: &mut W, data: &[u8]) -> std::io::Result<()> {
     w.write_all(&vec![0; extra_bytes])?;
     Ok(())
 }
+
+/// Issues a read of `len` bytes, rounded up to a multiple of 4, at `addr`.
+pub fn read_req<W: Write>(w: &mut W, addr: u64, len: usize) -> std::io::Result<()> {
+    w.write_all(&Command::Read.to_u32().to_le_bytes())?;
+    w.write_all(&addr.to_le_bytes())?;
+
+    let num_words = std::cmp::max(len.div_ceil(4), 1);
+    w.write_all(&(num_words - 1).to_le_bytes())?;
+
+    Ok(())
+}
+
+/// A TSI connection over a byte stream, such as a serial port.
+pub struct Tsi<T> {
+    port: T,
+}
+
+impl<T: Read + Write> Tsi<T> {
+    pub fn new(port: T) -> Self {
+        Self { port }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.port
+    }
+
+    /// Reads `len` bytes at `addr`, rounded up to a multiple of 4.
+    pub fn read(&mut self, addr: u64, len: usize) -> io::Result<Vec<u8>> {
+        read_req(&mut self.port, addr, len)?;
+        let mut data = vec![0; std::cmp::max(len.div_ceil(4), 1) * 4];
+        self.port.read_exact(&mut data)?;
+        Ok(data)
+    }
+
+    pub fn write(&mut self, addr: u64, data: &[u8]) -> io::Result<()> {
+        write_req(&mut self.port, Command::Write, addr, data)?;
+        self.port.flush()
+    }
+
+    pub fn read_u64(&mut self, addr: u64) -> io::Result<u64> {
+        let data = self.read(addr, 8)?;
+        Ok(u64::from_le_bytes(data.try_into().unwrap()))
+    }
+
+    pub fn write_u64(&mut self, addr: u64, data: u64) -> io::Result<()> {
+        self.write(addr, &data.to_le_bytes())
+    }
+
+    /// Reads `addr` until `done` holds for its value, failing with
+    /// [`io::ErrorKind::TimedOut`] if `timeout` elapses first.
+    pub fn poll_u64(
+        &mut self,
+        addr: u64,
+        timeout: Duration,
+        done: impl Fn(u64) -> bool,
+    ) -> io::Result<u64> {
+        let start = Instant::now();
+        loop {
+            let value = self.read_u64(addr)?;
+            if done(value) {
+                return Ok(value);
+            }
+            if start.elapsed() >= timeout {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("timed out polling {addr:#x}"),
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//! Driver for the FPGA-side `StacController`, which drives the STAC board's
+//! SRAM and PLL control pins.
+
+use crate::Tsi;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::thread;
+use std::time::Duration;
+
+/// The base address of the `StacController` peripheral.
+pub const STAC_CONTROLLER_BASE: u64 = 0x9000_0000;
+
+/// The frequency of the FPGA clock that the chip clock is divided from.
+pub const SOURCE_CLOCK_HZ: u64 = 50_000_000;
+
+/// Register offsets from `StacControllerCtrlRegs.REGMAP_OFFSET`, generated
+/// from the Scala RTL by `build.rs`.
+pub mod regs {
+    include!(concat!(env!("OUT_DIR"), "/regs.rs"));
+}
+
+/// Who controls the STAC test SRAMs, selected by `SRAM_EXT_EN` and
+/// `SRAM_SCAN_MODE`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum SramMode {
+    /// The chip's MMIO registers.
+    Internal,
+    /// The controller's pins, with configuration from the chip's MMIO registers.
+    External,
+    /// The controller's pins, with configuration from the scan chain.
+    Scan,
+}
+
+/// The `HALF_CLK_DIV_RATIO` giving the chip clock frequency nearest to `hz`.
+///
+/// The controller toggles the chip clock every `HALF_CLK_DIV_RATIO` source
+/// cycles, so the chip clock runs at `SOURCE_CLOCK_HZ / ratio / 2`.
+pub fn half_clk_div_ratio(hz: u64) -> u32 {
+    assert!(hz > 0, "clock frequency must be positive");
+    let (source, hz) = (SOURCE_CLOCK_HZ as u128, hz as u128);
+    let floor = source / (2 * hz);
+    if floor == 0 {
+        return 1;
+    }
+    // The frequencies of `floor` and `floor + 1` lie on either side of `hz`.
+    // `floor` is nearer iff the mean of the two is at most `hz`.
+    let ceil = floor + 1;
+    let ratio = if source * (floor + ceil) <= 4 * hz * floor * ceil {
+        floor
+    } else {
+        ceil
+    };
+    ratio.min(u32::MAX as u128) as u32
+}
+
+/// The chip clock frequency produced by a `HALF_CLK_DIV_RATIO`.
+pub fn clock_hz(half_clk_div_ratio: u32) -> f64 {
+    // A ratio of 0 toggles every cycle, like a ratio of 1.
+    SOURCE_CLOCK_HZ as f64 / half_clk_div_ratio.max(1) as f64 / 2.0
+}
+
+/// A snapshot of the `StacController` registers.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct StacStatus {
+    pub sram_ext_en: bool,
+    pub sram_scan_mode: bool,
+    pub sram_en: bool,
+    pub sram_bist_en: bool,
+    pub sram_bist_start: bool,
+    pub sram_bist_done: bool,
+    pub pll_sel: bool,
+    pub pll_scan_rstn: bool,
+    pub pll_arstb: bool,
+    pub clk_en: bool,
+    pub half_clk_div_ratio: u32,
+}
+
+impl StacStatus {
+    /// The SRAM mode selected by `SRAM_EXT_EN` and `SRAM_SCAN_MODE`, or
+    /// `None` if scan mode is set without external control.
+    pub fn sram_mode(&self) -> Option<SramMode> {
+        match (self.sram_ext_en, self.sram_scan_mode) {
+            (false, false) => Some(SramMode::Internal),
+            (true, false) => Some(SramMode::External),
+            (true, true) => Some(SramMode::Scan),
+            (false, true) => None,
+        }
+    }
+
+    /// The chip clock frequency, whether or not the clock is enabled.
+    pub fn clock_hz(&self) -> f64 {
+        clock_hz(self.half_clk_div_ratio)
+    }
+}
+
+impl fmt::Display for StacStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bit = |b: bool| b as u8;
+        writeln!(
+            f,
+            "clock: {} at {:.3} kHz (HALF_CLK_DIV_RATIO {})",
+            if self.clk_en { "enabled" } else { "disabled" },
+            self.clock_hz() / 1e3,
+            self.half_clk_div_ratio
+        )?;
+        match self.sram_mode() {
+            Some(mode) => writeln!(f, "SRAM mode: {mode:?}")?,
+            None => writeln!(f, "SRAM mode: invalid (SRAM_SCAN_MODE without SRAM_EXT_EN)")?,
+        }
+        writeln!(
+            f,
+            "SRAM_EN {} SRAM_BIST_EN {} SRAM_BIST_START {} SRAM_BIST_DONE {}",
+            bit(self.sram_en),
+            bit(self.sram_bist_en),
+            bit(self.sram_bist_start),
+            bit(self.sram_bist_done)
+        )?;
+        writeln!(
+            f,
+            "PLL_SEL {} PLL_SCAN_RSTN {} PLL_ARSTB {}",
+            bit(self.pll_sel),
+            bit(self.pll_scan_rstn),
+            bit(self.pll_arstb)
+        )
+    }
+}
+
+/// The `StacController` peripheral, accessed over TSI.
+pub struct StacController<T> {
+    tsi: Tsi<T>,
+}
+
+impl<T: Read + Write> StacController<T> {
+    pub fn new(tsi: Tsi<T>) -> Self {
+        Self { tsi }
+    }
+
+    pub fn into_inner(self) -> Tsi<T> {
+        self.tsi
+    }
+
+    pub fn read_reg(&mut self, offset: u64) -> io::Result<u64> {
+        self.tsi.read_u64(STAC_CONTROLLER_BASE + offset)
+    }
+
+    pub fn write_reg(&mut self, offset: u64, value: u64) -> io::Result<()> {
+        self.tsi.write_u64(STAC_CONTROLLER_BASE + offset, value)
+    }
+
+    fn read_bit(&mut self, offset: u64) -> io::Result<bool> {
+        Ok(self.read_reg(offset)? & 1 != 0)
+    }
+
+    /// Sets the chip clock to the frequency nearest to `hz` that the divider
+    /// can produce, returning that frequency.
+    pub fn set_clock_hz(&mut self, hz: u64) -> io::Result<f64> {
+        let ratio = half_clk_div_ratio(hz);
+        self.write_reg(regs::HALF_CLK_DIV_RATIO, ratio as u64)?;
+        Ok(clock_hz(ratio))
+    }
+
+    /// Reads back the chip clock frequency set by the divider.
+    pub fn clock_hz(&mut self) -> io::Result<f64> {
+        Ok(clock_hz(self.read_reg(regs::HALF_CLK_DIV_RATIO)? as u32))
+    }
+
+    /// Gates the chip clock on or off. The chip's UART beacon only runs
+    /// while the clock is enabled.
+    pub fn enable_clock(&mut self, enable: bool) -> io::Result<()> {
+        self.write_reg(regs::CLK_EN, enable as u64)
+    }
+
+    pub fn enable_sram(&mut self, enable: bool) -> io::Result<()> {
+        self.write_reg(regs::SRAM_EN, enable as u64)
+    }
+
+    pub fn set_pll_sel(&mut self, sel: bool) -> io::Result<()> {
+        self.write_reg(regs::PLL_SEL, sel as u64)
+    }
+
+    /// Resets the PLL by holding `PLL_ARSTB` and `PLL_SCAN_RSTN` low for
+    /// `hold`, then releasing the scan reset followed by the PLL reset.
+    pub fn pll_reset_sequence(&mut self, hold: Duration) -> io::Result<()> {
+        self.write_reg(regs::PLL_ARSTB, 0)?;
+        self.write_reg(regs::PLL_SCAN_RSTN, 0)?;
+        thread::sleep(hold);
+        self.write_reg(regs::PLL_SCAN_RSTN, 1)?;
+        self.write_reg(regs::PLL_ARSTB, 1)
+    }
+
+    /// Reads back every register.
+    pub fn status(&mut self) -> io::Result<StacStatus> {
+        Ok(StacStatus {
+            sram_ext_en: self.read_bit(regs::SRAM_EXT_EN)?,
+            sram_scan_mode: self.read_bit(regs::SRAM_SCAN_MODE)?,
+            sram_en: self.read_bit(regs::SRAM_EN)?,
+            sram_bist_en: self.read_bit(regs::SRAM_BIST_EN)?,
+            sram_bist_start: self.read_bit(regs::SRAM_BIST_START)?,
+            sram_bist_done: self.read_bit(regs::SRAM_BIST_DONE)?,
+            pll_sel: self.read_bit(regs::PLL_SEL)?,
+            pll_scan_rstn: self.read_bit(regs::PLL_SCAN_RSTN)?,
+            pll_arstb: self.read_bit(regs::PLL_ARSTB)?,
+            clk_en: self.read_bit(regs::CLK_EN)?,
+            half_clk_div_ratio: self.read_reg(regs::HALF_CLK_DIV_RATIO)? as u32,
+        })
+    }
+
+    pub fn set_sram_mode(&mut self, mode: SramMode) -> io::Result<()> {
+        let (ext_en, scan_mode) = match mode {
+            SramMode::Internal => (0, 0),
+            SramMode::External => (1, 0),
+            SramMode::Scan => (1, 1),
+        };
+        self.write_reg(regs::SRAM_SCAN_MODE, scan_mode)?;
+        self.write_reg(regs::SRAM_EXT_EN, ext_en)
+    }
+
+    /// Raises `SRAM_BIST_START` with `SRAM_BIST_EN` set, starting the BIST
+    /// engine with its currently loaded configuration.
+    ///
+    /// Fails without raising `SRAM_BIST_START` if `SRAM_BIST_DONE` is
+    /// already high, since [`StacController::wait_bist_done`] could not tell
+    /// the new run finishing from the stale result.
+    pub fn bist_start(&mut self) -> io::Result<()> {
+        self.write_reg(regs::SRAM_BIST_START, 0)?;
+        self.write_reg(regs::SRAM_BIST_EN, 1)?;
+        if self.bist_done()? {
+            return Err(io::Error::other(
+                "SRAM_BIST_DONE is high before SRAM_BIST_START was raised",
+            ));
+        }
+        self.write_reg(regs::SRAM_BIST_START, 1)
+    }
+
+    /// Lowers `SRAM_BIST_START` and `SRAM_BIST_EN`.
+    pub fn bist_stop(&mut self) -> io::Result<()> {
+        self.write_reg(regs::SRAM_BIST_START, 0)?;
+        self.write_reg(regs::SRAM_BIST_EN, 0)
+    }
+
+    /// Samples the `SRAM_BIST_DONE` pin.
+    pub fn bist_done(&mut self) -> io::Result<bool> {
+        self.read_bit(regs::SRAM_BIST_DONE)
+    }
+
+    /// Waits for `SRAM_BIST_DONE`, failing with [`io::ErrorKind::TimedOut`]
+    /// if `timeout` elapses first.
+    pub fn wait_bist_done(&mut self, timeout: Duration) -> io::Result<()> {
+        self.tsi
+            .poll_u64(
+                STAC_CONTROLLER_BASE + regs::SRAM_BIST_DONE,
+                timeout,
+                |done| done & 1 != 0,
+            )
+            .map(|_| ())
+    }
+}
@@ -0,0 +1,96 @@
+use crate::fake::FakeTsiDevice;
+use crate::stac::{half_clk_div_ratio, regs, SramMode, StacController, STAC_CONTROLLER_BASE};
+use crate::Tsi;
+use std::time::Duration;
+
+#[test]
+fn stac_controller_bist_pins() {
+    let mut controller = StacController::new(Tsi::new(FakeTsiDevice::default()));
+    controller.set_sram_mode(SramMode::External).unwrap();
+    assert!(!controller.bist_done().unwrap());
+    controller.bist_start().unwrap();
+    controller
+        .wait_bist_done(Duration::from_secs(1))
+        .expect("BIST should finish");
+    controller.bist_stop().unwrap();
+
+    let device = controller.into_inner().into_inner();
+    let writes: Vec<(u64, u64)> = device
+        .writes()
+        .iter()
+        .map(|&(addr, value)| (addr - STAC_CONTROLLER_BASE, value))
+        .collect();
+    assert_eq!(
+        writes,
+        vec![
+            (regs::SRAM_SCAN_MODE, 0),
+            (regs::SRAM_EXT_EN, 1),
+            (regs::SRAM_BIST_START, 0),
+            (regs::SRAM_BIST_EN, 1),
+            (regs::SRAM_BIST_START, 1),
+            (regs::SRAM_BIST_START, 0),
+            (regs::SRAM_BIST_EN, 0),
+        ]
+    );
+
+    // Without a BIST start, `SRAM_BIST_DONE` never rises.
+    let mut controller = StacController::new(Tsi::new(FakeTsiDevice::default()));
+    let err = controller
+        .wait_bist_done(Duration::from_millis(10))
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+    // A stale `SRAM_BIST_DONE` is caught before the engine is started.
+    let mut controller = StacController::new(Tsi::new(FakeTsiDevice::with_bist_done_stuck()));
+    assert!(controller.bist_done().unwrap());
+    controller.bist_start().unwrap_err();
+    let device = controller.into_inner().into_inner();
+    assert!(!device
+        .writes()
+        .contains(&(STAC_CONTROLLER_BASE + regs::SRAM_BIST_START, 1)));
+}
+
+#[test]
+fn stac_controller_clock() {
+    assert_eq!(half_clk_div_ratio(200_000), 125);
+    assert_eq!(half_clk_div_ratio(25_000_000), 1);
+    assert_eq!(half_clk_div_ratio(100_000_000), 1);
+    // 50 MHz / 2 / 300 kHz is 83.3: 301.2 kHz is nearer than 297.6 kHz.
+    assert_eq!(half_clk_div_ratio(300_000), 83);
+    // A ratio of 2.45 rounds to 2, but 8.33 MHz is nearer than 12.5 MHz.
+    assert_eq!(half_clk_div_ratio(10_204_082), 3);
+
+    let mut controller = StacController::new(Tsi::new(FakeTsiDevice::default()));
+    let achieved = controller.set_clock_hz(300_000).unwrap();
+    assert!((achieved - 301_204.8).abs() < 0.1);
+    controller.enable_clock(true).unwrap();
+    controller.set_sram_mode(SramMode::Scan).unwrap();
+    controller
+        .pll_reset_sequence(Duration::from_millis(1))
+        .unwrap();
+
+    let status = controller.status().unwrap();
+    assert!(status.clk_en);
+    assert_eq!(status.half_clk_div_ratio, 83);
+    assert_eq!(controller.clock_hz().unwrap(), achieved);
+    assert_eq!(status.sram_mode(), Some(SramMode::Scan));
+    assert!(status.pll_arstb && status.pll_scan_rstn);
+    assert!(status.to_string().contains("enabled at 301.205 kHz"));
+
+    let device = controller.into_inner().into_inner();
+    let pll: Vec<(u64, u64)> = device
+        .writes()
+        .iter()
+        .map(|&(addr, value)| (addr - STAC_CONTROLLER_BASE, value))
+        .filter(|&(offset, _)| offset == regs::PLL_ARSTB || offset == regs::PLL_SCAN_RSTN)
+        .collect();
+    assert_eq!(
+        pll,
+        vec![
+            (regs::PLL_ARSTB, 0),
+            (regs::PLL_SCAN_RSTN, 0),
+            (regs::PLL_SCAN_RSTN, 1),
+            (regs::PLL_ARSTB, 1),
+        ]
+    );
+}